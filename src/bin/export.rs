@@ -0,0 +1,45 @@
+use bevy_retro_racing::prelude::*;
+use clap::Parser;
+use std::io;
+use std::path::PathBuf;
+
+/// Renders a recorded run to an animated GIF or PNG frames, without a window.
+#[derive(Parser)]
+struct Args {
+    /// Replay to render, the last finished run by default
+    replay: Option<PathBuf>,
+
+    /// A `.gif` file, or a directory to fill with PNG frames
+    #[arg(short, long)]
+    output: PathBuf,
+
+    /// Colors to draw with, the ones from the settings by default
+    #[arg(long, value_enum)]
+    theme: Option<ThemePreset>,
+
+    /// Image pixels per pixel of the in-game tiles
+    #[arg(long, default_value_t = 1, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..=8))]
+    scale: usize,
+}
+
+fn main() {
+    if let Err(err) = run(Args::parse()) {
+        eprintln!("error: {}", err);
+        std::process::exit(1);
+    }
+}
+
+fn run(args: Args) -> io::Result<()> {
+    let path = args
+        .replay
+        .or_else(|| config_path(LAST_RUN_FILE))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no replay given"))?;
+    let replay = Replay::load(&path)
+        .map_err(|err| io::Error::new(err.kind(), format!("{}: {}", path.display(), err)))?;
+    let theme = Theme::preset(args.theme.unwrap_or_else(|| Settings::load_user().theme));
+
+    let frames = export_replay(&replay, &theme, args.scale, &args.output)?;
+    println!("{} frames written to {}", frames, args.output.display());
+
+    Ok(())
+}
@@ -0,0 +1,136 @@
+use bevy_retro_racing::prelude::*;
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, Write};
+
+/// Drives the game one step at a time from JSON lines on stdin.
+///
+/// Requests are `{"cmd": "reset", "seed": 7}`, `{"cmd": "step", "action": "left"}`
+/// (`none`, `left`, `right` or `boost`) and `{"cmd": "close"}`. Every reply is
+/// one JSON line with the observation grid, reward and done flag, or an error.
+#[derive(Parser)]
+struct Args {
+    /// Number of lanes on the road
+    #[arg(long, default_value_t = LANES, value_parser = lanes_parser())]
+    lanes: usize,
+
+    /// Starting speed level
+    #[arg(long, default_value_t = 0)]
+    level: usize,
+
+    /// Frames every action is held for
+    #[arg(long, default_value_t = 4)]
+    frame_skip: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "cmd", rename_all = "lowercase")]
+enum Request {
+    Reset { seed: Option<u64> },
+    Step { action: Action },
+    Close,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Reply {
+    Step {
+        observation: Observation,
+        reward: f32,
+        done: bool,
+        score: usize,
+        distance: usize,
+    },
+    Error {
+        error: String,
+    },
+}
+
+fn reply(env: &RaceEnv, observation: Observation, reward: f32, done: bool) -> Reply {
+    Reply::Step {
+        observation,
+        reward,
+        done,
+        score: env.race.score,
+        distance: env.race.distance,
+    }
+}
+
+fn main() -> io::Result<()> {
+    let args = Args::parse();
+    let mut env = RaceEnv::new(
+        RaceConfig {
+            lanes: args.lanes,
+            level: args.level,
+            ..default()
+        },
+        args.frame_skip,
+    );
+
+    let mut stdout = io::stdout().lock();
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str(&line) {
+            Ok(Request::Reset { seed }) => {
+                let observation = env.reset(seed.unwrap_or_else(|| thread_rng().gen()));
+                reply(&env, observation, 0.0, false)
+            }
+            Ok(Request::Step { .. }) if env.race.is_over() => Reply::Error {
+                error: "run is over, send a reset".to_string(),
+            },
+            Ok(Request::Step { action }) => {
+                let (observation, reward, done) = env.step(action);
+                reply(&env, observation, reward, done)
+            }
+            Ok(Request::Close) => break,
+            Err(err) => Reply::Error {
+                error: err.to_string(),
+            },
+        };
+
+        serde_json::to_writer(&mut stdout, &response)?;
+        writeln!(stdout)?;
+        stdout.flush()?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_and_replies_are_json_lines() {
+        let Ok(Request::Reset { seed: Some(7) }) =
+            serde_json::from_str(r#"{"cmd": "reset", "seed": 7}"#)
+        else {
+            panic!("reset with a seed");
+        };
+        let Ok(Request::Step {
+            action: Action::Left,
+        }) = serde_json::from_str(r#"{"cmd": "step", "action": "left"}"#)
+        else {
+            panic!("step left");
+        };
+        assert!(serde_json::from_str::<Request>(r#"{"cmd": "jump"}"#).is_err());
+
+        let env = RaceEnv::new(RaceConfig::default(), 1);
+        let reply =
+            serde_json::to_value(reply(&env, vec![vec![0, 1]], CRASH_REWARD, true)).unwrap();
+        assert_eq!(
+            reply,
+            serde_json::json!({
+                "observation": [[0, 1]],
+                "reward": CRASH_REWARD,
+                "done": true,
+                "score": 0,
+                "distance": 0,
+            })
+        );
+    }
+}
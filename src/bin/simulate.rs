@@ -0,0 +1,234 @@
+use bevy_retro_racing::prelude::*;
+use clap::{Parser, ValueEnum};
+use serde::Serialize;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Plays seeded runs without a window and reports how long they last.
+#[derive(Parser)]
+struct Args {
+    /// Runs to play for every policy and level
+    #[arg(short, long, default_value_t = 1000)]
+    games: u64,
+
+    /// Seed of the first run, the others count up from it
+    #[arg(short, long, default_value_t = 0)]
+    seed: u64,
+
+    /// Bot policies driving the car
+    #[arg(
+        short,
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_value = "random,greedy,perfect"
+    )]
+    policy: Vec<Policy>,
+
+    /// Starting speed levels
+    #[arg(short, long, value_delimiter = ',', default_value = "0")]
+    level: Vec<usize>,
+
+    /// Number of lanes on the road
+    #[arg(long, default_value_t = LANES, value_parser = lanes_parser())]
+    lanes: usize,
+
+    /// Simulated frames per second
+    #[arg(long, default_value_t = 60, value_parser = clap::builder::RangedU64ValueParser::<u32>::new().range(1..))]
+    fps: u32,
+
+    /// Runs still going after this many simulated seconds are stopped
+    #[arg(long, default_value_t = 600)]
+    max_seconds: u64,
+
+    #[arg(short, long, value_enum, default_value_t = Format::Csv)]
+    format: Format,
+
+    /// Write the statistics here instead of stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Csv,
+    Json,
+}
+
+#[derive(Serialize)]
+struct Run {
+    policy: Policy,
+    level: usize,
+    seed: u64,
+    seconds: f32,
+    distance: usize,
+    score: usize,
+    speed: f32,
+    crash: Option<CrashCause>,
+    crash_lane: Option<usize>,
+    boosting: bool,
+}
+
+#[derive(Serialize)]
+struct Summary {
+    policy: Policy,
+    level: usize,
+    runs: usize,
+    survived: usize,
+    rammed: usize,
+    swerved: usize,
+    mean_seconds: f32,
+    median_seconds: f32,
+    max_speed: f32,
+    score_min: usize,
+    score_p25: usize,
+    score_median: usize,
+    score_p75: usize,
+    score_max: usize,
+}
+
+#[derive(Serialize)]
+struct Report {
+    runs: Vec<Run>,
+    summary: Vec<Summary>,
+}
+
+fn play(args: &Args, policy: Policy, level: usize, seed: u64) -> Run {
+    let delta = Duration::from_secs(1) / args.fps;
+    let limit = Duration::from_secs(args.max_seconds);
+    let mut race = Race::new(RaceConfig {
+        lanes: args.lanes,
+        level,
+        seed,
+    });
+    let mut bot = Bot::new(policy, seed);
+
+    while !race.is_over() && race.elapsed < limit {
+        race.steer(bot.decide(&race, delta));
+        race.update(delta);
+    }
+
+    Run {
+        policy,
+        level,
+        seed,
+        seconds: race.elapsed.as_secs_f32(),
+        distance: race.distance,
+        score: race.score,
+        speed: race.speed_factor,
+        crash: race.crash.map(|crash| crash.cause),
+        crash_lane: race.crash.map(|crash| crash.lane),
+        boosting: race.crash.is_some_and(|crash| crash.boosting),
+    }
+}
+
+fn percentile(sorted: &[usize], p: usize) -> usize {
+    sorted[(sorted.len() - 1) * p / 100]
+}
+
+fn summarize(runs: &[Run]) -> Summary {
+    let count = |cause| runs.iter().filter(|run| run.crash == cause).count();
+
+    let mut seconds: Vec<f32> = runs.iter().map(|run| run.seconds).collect();
+    seconds.sort_by(f32::total_cmp);
+    let mut scores: Vec<usize> = runs.iter().map(|run| run.score).collect();
+    scores.sort_unstable();
+
+    Summary {
+        policy: runs[0].policy,
+        level: runs[0].level,
+        runs: runs.len(),
+        survived: count(None),
+        rammed: count(Some(CrashCause::Rammed)),
+        swerved: count(Some(CrashCause::Swerved)),
+        mean_seconds: seconds.iter().sum::<f32>() / seconds.len() as f32,
+        median_seconds: seconds[seconds.len() / 2],
+        max_speed: runs.iter().map(|run| run.speed).fold(0.0, f32::max),
+        score_min: percentile(&scores, 0),
+        score_p25: percentile(&scores, 25),
+        score_median: percentile(&scores, 50),
+        score_p75: percentile(&scores, 75),
+        score_max: percentile(&scores, 100),
+    }
+}
+
+fn write_csv(out: &mut impl Write, report: &Report) -> io::Result<()> {
+    writeln!(
+        out,
+        "policy,level,seed,seconds,distance,score,speed,crash,crash_lane,boosting"
+    )?;
+    for run in &report.runs {
+        writeln!(
+            out,
+            "{},{},{},{:.3},{},{},{:.2},{},{},{}",
+            format!("{:?}", run.policy).to_lowercase(),
+            run.level,
+            run.seed,
+            run.seconds,
+            run.distance,
+            run.score,
+            run.speed,
+            run.crash
+                .map_or("none".to_string(), |cause| format!("{:?}", cause)
+                    .to_lowercase()),
+            run.crash_lane
+                .map_or(String::new(), |lane| lane.to_string()),
+            run.boosting,
+        )?;
+    }
+
+    Ok(())
+}
+
+fn main() -> io::Result<()> {
+    let args = Args::parse();
+
+    let mut report = Report {
+        runs: Vec::new(),
+        summary: Vec::new(),
+    };
+    for &policy in &args.policy {
+        for &level in &args.level {
+            let runs: Vec<Run> = (0..args.games)
+                .map(|game| play(&args, policy, level, args.seed.wrapping_add(game)))
+                .collect();
+
+            if !runs.is_empty() {
+                report.summary.push(summarize(&runs));
+            }
+            report.runs.extend(runs);
+        }
+    }
+
+    let mut out: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+    match args.format {
+        Format::Csv => write_csv(&mut out, &report)?,
+        Format::Json => serde_json::to_writer_pretty(&mut out, &report)?,
+    }
+    out.flush()?;
+
+    for summary in &report.summary {
+        eprintln!(
+            "{:?} level {}: {} runs, {:.1}s mean, {:.1}s median, score {}..{} (median {}), speed x{:.2}, {} survived, {} rammed, {} swerved",
+            summary.policy,
+            summary.level,
+            summary.runs,
+            summary.mean_seconds,
+            summary.median_seconds,
+            summary.score_min,
+            summary.score_max,
+            summary.score_median,
+            summary.max_speed,
+            summary.survived,
+            summary.rammed,
+            summary.swerved,
+        );
+    }
+
+    Ok(())
+}
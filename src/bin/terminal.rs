@@ -0,0 +1,142 @@
+use bevy_retro_racing::prelude::*;
+use clap::Parser;
+use std::io::{self, Read, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const FRAME: Duration = Duration::from_micros(16_667);
+
+/// Plays the racer in a text console, for sessions without a display.
+///
+/// Steer with the arrow keys (or A/D), toggle boost with space, pause with P
+/// and quit with Q or Escape.
+#[derive(Parser)]
+struct Args {
+    /// Number of lanes on the road
+    #[arg(long, default_value_t = LANES, value_parser = lanes_parser())]
+    lanes: usize,
+
+    /// Starting speed level
+    #[arg(long, default_value_t = 0)]
+    level: usize,
+
+    /// Seed of the first run, random by default
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
+#[cfg(unix)]
+mod raw_mode {
+    use std::io;
+    use std::mem::MaybeUninit;
+
+    /// Puts the terminal into unbuffered, silent, non-blocking input until
+    /// dropped.
+    pub struct RawMode {
+        original: libc::termios,
+    }
+
+    impl RawMode {
+        pub fn enable() -> io::Result<Self> {
+            let mut termios = MaybeUninit::uninit();
+            // SAFETY: tcgetattr fills the struct when it returns 0.
+            let original = unsafe {
+                if libc::tcgetattr(libc::STDIN_FILENO, termios.as_mut_ptr()) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                termios.assume_init()
+            };
+
+            let mut raw = original;
+            raw.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG | libc::IEXTEN);
+            raw.c_iflag &= !(libc::IXON | libc::ICRNL);
+            raw.c_cc[libc::VMIN] = 0;
+            raw.c_cc[libc::VTIME] = 0;
+            // SAFETY: `raw` is a valid termios copied from the terminal's own.
+            if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) } != 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            Ok(Self { original })
+        }
+    }
+
+    impl Drop for RawMode {
+        fn drop(&mut self) {
+            // SAFETY: restores the settings read in `enable`.
+            unsafe {
+                libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original);
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn main() -> io::Result<()> {
+    let args = Args::parse();
+    let mut race = Race::new(RaceConfig {
+        lanes: args.lanes,
+        level: args.level,
+        seed: args.seed.unwrap_or_else(rand::random),
+    });
+
+    let _raw_mode = raw_mode::RawMode::enable()?;
+    let mut stdin = io::stdin();
+    let mut stdout = io::stdout();
+    // Hide the cursor and clear the screen; undone on the way out.
+    write!(stdout, "\x1b[?25l\x1b[2J")?;
+
+    let result = play(&mut race, &mut stdin, &mut stdout);
+
+    write!(stdout, "\x1b[?25h\r\n")?;
+    stdout.flush()?;
+    result
+}
+
+#[cfg(unix)]
+fn play(race: &mut Race, stdin: &mut impl Read, stdout: &mut impl Write) -> io::Result<()> {
+    let mut highscore = 0;
+    let mut paused = false;
+    let mut input = [0; 64];
+    let mut last_frame = Instant::now();
+
+    loop {
+        let read = stdin.read(&mut input)?;
+        for key in parse_keys(&input[..read]) {
+            match key {
+                TerminalKey::Quit => return Ok(()),
+                TerminalKey::Pause if !race.is_over() => paused = !paused,
+                TerminalKey::Boost if race.is_over() => race.restart(),
+                TerminalKey::Boost if !paused => race.is_boosting = !race.is_boosting,
+                TerminalKey::Left if !paused => {
+                    race.steer(Steer::Left);
+                }
+                TerminalKey::Right if !paused => {
+                    race.steer(Steer::Right);
+                }
+                _ => {}
+            }
+        }
+
+        let now = Instant::now();
+        if !paused {
+            race.update(now - last_frame);
+        }
+        last_frame = now;
+        if race.is_over() {
+            highscore = highscore.max(race.score);
+        }
+
+        write!(stdout, "\x1b[H{}", draw_frame(race, highscore, paused))?;
+        stdout.flush()?;
+
+        thread::sleep(FRAME.saturating_sub(last_frame.elapsed()));
+    }
+}
+
+#[cfg(not(unix))]
+fn main() {
+    let _ = Args::parse();
+    eprintln!("error: the terminal front-end needs a Unix terminal");
+    std::process::exit(1);
+}
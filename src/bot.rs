@@ -0,0 +1,183 @@
+use crate::prelude::*;
+use rand::rngs::StdRng;
+use std::time::Duration;
+
+const RANDOM_STEER_CHANCE: f64 = 0.05;
+const GREEDY_LOOKAHEAD: i32 = 8;
+const PLAN_FRAMES: usize = 120;
+const PLAN_ROWS: i32 = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Policy {
+    /// Steers at random now and then.
+    Random,
+    /// Dodges the nearest car in its lane into the emptiest neighbour lane.
+    Greedy,
+    /// Plans frame by frame over every visible car.
+    Perfect,
+}
+
+pub struct Bot {
+    pub policy: Policy,
+    rng: StdRng,
+}
+
+impl Bot {
+    pub fn new(policy: Policy, seed: u64) -> Self {
+        Self {
+            policy,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Picks the steering for the next frame, which will last `delta`.
+    pub fn decide(&mut self, race: &Race, delta: Duration) -> Steer {
+        match self.policy {
+            Policy::Random => self.random(),
+            Policy::Greedy => greedy(race),
+            Policy::Perfect => perfect(race, delta),
+        }
+    }
+
+    fn random(&mut self) -> Steer {
+        if !self.rng.gen_bool(RANDOM_STEER_CHANCE) {
+            Steer::Straight
+        } else if self.rng.gen() {
+            Steer::Left
+        } else {
+            Steer::Right
+        }
+    }
+}
+
+fn neighbours(race: &Race, lane: usize) -> impl Iterator<Item = (Steer, usize)> {
+    let lanes = race.config.lanes;
+
+    [
+        (Steer::Straight, Some(lane)),
+        (Steer::Left, lane.checked_sub(1)),
+        (Steer::Right, Some(lane + 1).filter(|&l| l < lanes)),
+    ]
+    .into_iter()
+    .filter_map(|(steer, lane)| lane.map(|lane| (steer, lane)))
+}
+
+/// Rows to the closest rival in `lane` that has not gone past the player yet.
+fn clearance(race: &Race, lane: usize) -> i32 {
+    race.rivals
+        .iter()
+        .filter(|rival| rival.lane == lane && rival.row >= *HIT_ROWS.start())
+        .map(|rival| rival.row)
+        .min()
+        .unwrap_or(i32::MAX)
+}
+
+fn greedy(race: &Race) -> Steer {
+    if clearance(race, race.lane) > GREEDY_LOOKAHEAD {
+        return Steer::Straight;
+    }
+
+    neighbours(race, race.lane)
+        .max_by_key(|&(_, lane)| clearance(race, lane))
+        .map_or(Steer::Straight, |(steer, _)| steer)
+}
+
+fn perfect(race: &Race, delta: Duration) -> Steer {
+    // Rows the road will have scrolled after each of the coming frames.
+    let mut timer = race.move_timer().clone();
+    let mut ahead = 0;
+    let mut rows = Vec::with_capacity(PLAN_FRAMES);
+    while rows.len() < PLAN_FRAMES && ahead < PLAN_ROWS {
+        timer.tick(delta.mul_f32(race.pace()));
+        if timer.finished() {
+            ahead += 1;
+        }
+        rows.push(ahead);
+    }
+
+    // Frames each lane can survive from a given frame on, playing optimally.
+    let lanes = race.config.lanes;
+    let mut survival = vec![0; lanes];
+    for &ahead in rows.iter().rev() {
+        survival = (0..lanes)
+            .map(|lane| {
+                if race.is_blocked(lane, ahead) {
+                    0
+                } else {
+                    1 + neighbours(race, lane)
+                        .map(|(_, next)| survival[next])
+                        .max()
+                        .unwrap_or(0)
+                }
+            })
+            .collect();
+    }
+
+    let centre = lanes / 2;
+    neighbours(race, race.lane)
+        .max_by_key(|&(steer, lane)| {
+            (
+                survival[lane],
+                steer == Steer::Straight,
+                std::cmp::Reverse(lane.abs_diff(centre)),
+            )
+        })
+        .map_or(Steer::Straight, |(steer, _)| steer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: Duration = Duration::from_millis(16);
+    const LIMIT: Duration = Duration::from_secs(60);
+
+    fn run(policy: Policy, seed: u64) -> Race {
+        let mut race = Race::new(RaceConfig {
+            lanes: LANES,
+            level: 0,
+            seed,
+        });
+        let mut bot = Bot::new(policy, seed);
+
+        while !race.is_over() && race.elapsed < LIMIT {
+            race.steer(bot.decide(&race, FRAME));
+            race.update(FRAME);
+        }
+        race
+    }
+
+    #[test]
+    fn seeded_runs_repeat_exactly() {
+        for policy in [Policy::Random, Policy::Greedy, Policy::Perfect] {
+            let (first, second) = (run(policy, 9), run(policy, 9));
+            assert_eq!(
+                (first.elapsed, first.score, first.crash),
+                (second.elapsed, second.score, second.crash),
+                "{:?}",
+                policy
+            );
+        }
+    }
+
+    #[test]
+    fn perfect_outlasts_random() {
+        for seed in 0..4 {
+            let random = run(Policy::Random, seed);
+            let perfect = run(Policy::Perfect, seed);
+
+            assert!(random.is_over(), "seed {}", seed);
+            assert!(perfect.elapsed > random.elapsed, "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn a_crashed_run_says_why_and_where() {
+        let race = run(Policy::Random, 3);
+        let crash = race.crash.expect("random driving crashes within a minute");
+
+        assert_eq!(crash.lane, race.lane);
+        assert!(race.is_blocked(race.lane, 0));
+    }
+}
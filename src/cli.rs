@@ -0,0 +1,115 @@
+use crate::prelude::*;
+use clap::Parser;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Simulated time a headless bot run is cut off after.
+pub const HEADLESS_SECONDS: u64 = 600;
+pub const HEADLESS_FPS: u32 = 60;
+
+/// Parses a lane count within `LANE_RANGE`, for every binary's `--lanes`.
+pub fn lanes_parser() -> clap::builder::RangedU64ValueParser<usize> {
+    clap::builder::RangedU64ValueParser::new()
+        .range(*LANE_RANGE.start() as u64..=*LANE_RANGE.end() as u64)
+}
+
+/// Brick Game car racing.
+#[derive(Parser, Debug)]
+#[command(version)]
+pub struct LaunchOptions {
+    /// Seed for the first run, random if not given
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Starting speed level, each one makes the road 2% faster
+    #[arg(long, default_value_t = 0, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(0..=MAX_LEVEL as u64))]
+    pub level: usize,
+
+    /// Number of lanes on the road
+    #[arg(long, default_value_t = LANES, value_parser = lanes_parser())]
+    pub lanes: usize,
+
+    /// Start without sound, for this run only
+    #[arg(long)]
+    pub mute: bool,
+
+    /// Window scale factor, for this run only
+    #[arg(long, value_parser = parse_scale, conflicts_with = "headless")]
+    pub scale: Option<f64>,
+
+    /// Play back a recorded run
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["record", "seed", "level", "lanes"])]
+    pub replay: Option<PathBuf>,
+
+    /// Save every finished run to this file instead of the last-run file
+    #[arg(long, value_name = "FILE")]
+    pub record: Option<PathBuf>,
+
+    /// Run without a window: play the replay, or let the bot drive
+    #[arg(long)]
+    pub headless: bool,
+}
+
+fn parse_scale(value: &str) -> Result<f64, String> {
+    let scale: f64 = value
+        .parse()
+        .map_err(|_| format!("`{value}` is not a number"))?;
+    if (0.25..=8.0).contains(&scale) {
+        Ok(scale)
+    } else {
+        Err("scale must be between 0.25 and 8".to_string())
+    }
+}
+
+impl LaunchOptions {
+    pub fn race_config(&self) -> RaceConfig {
+        RaceConfig {
+            lanes: self.lanes,
+            level: self.level,
+            seed: self.seed.unwrap_or_else(|| thread_rng().gen()),
+        }
+    }
+
+    /// Plays one run with no window or audio and prints how it ended.
+    pub fn run_headless(&self) -> io::Result<()> {
+        let race = match &self.replay {
+            Some(path) => Replay::load(path)?.play(),
+            None => {
+                let delta = Duration::from_secs(1) / HEADLESS_FPS;
+                let limit = Duration::from_secs(HEADLESS_SECONDS);
+                let mut race = Race::new(self.race_config());
+                let mut bot = Bot::new(Policy::Perfect, race.config.seed);
+
+                if self.record.is_some() {
+                    race.record();
+                }
+                while !race.is_over() && race.elapsed < limit {
+                    race.steer(bot.decide(&race, delta));
+                    race.update(delta);
+                }
+
+                race
+            }
+        };
+
+        if let (Some(path), Some(replay)) = (&self.record, &race.replay) {
+            replay.save(path)?;
+        }
+
+        println!(
+            "seed {} score {} distance {} time {:.2}s speed x{:.2} {}",
+            race.config.seed,
+            race.score,
+            race.distance,
+            race.elapsed.as_secs_f32(),
+            race.speed_factor,
+            match race.crash {
+                Some(crash) => format!("crashed ({:?}) in lane {}", crash.cause, crash.lane),
+                None => "finished".to_string(),
+            },
+        );
+
+        Ok(())
+    }
+}
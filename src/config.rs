@@ -0,0 +1,49 @@
+use bevy::log::warn;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const APP_DIR: &str = "bevy_retro_racing";
+
+/// Where a user config file lives: `$XDG_CONFIG_HOME`, `~/.config` or
+/// `%APPDATA%`, in a directory of our own.
+pub fn config_path(file: &str) -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))?;
+
+    Some(base.join(APP_DIR).join(file))
+}
+
+/// Reads a RON config file. A missing file is `None` without a fuss, an
+/// unreadable or malformed one is `None` with a warning.
+pub fn read_ron<T: DeserializeOwned>(path: &Path) -> Option<T> {
+    match fs::read_to_string(path) {
+        Ok(text) => match ron::from_str(&text) {
+            Ok(value) => Some(value),
+            Err(err) => {
+                warn!("Ignoring {}: {}", path.display(), err);
+                None
+            }
+        },
+        Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+        Err(err) => {
+            warn!("Could not read {}: {}", path.display(), err);
+            None
+        }
+    }
+}
+
+pub fn write_ron<T: Serialize>(path: &Path, value: &T) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let text = ron::ser::to_string_pretty(value, Default::default())
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    fs::write(path, text)
+}
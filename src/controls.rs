@@ -0,0 +1,396 @@
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+pub const CONTROLS_FILE: &str = "controls.ron";
+/// How long the controls screen waits for a key before giving up. Any key,
+/// Escape included, can be bound while it waits.
+pub const LISTEN_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum InputAction {
+    SteerLeft,
+    SteerRight,
+    Boost,
+    Pause,
+    Confirm,
+    Mute,
+}
+
+impl InputAction {
+    pub const ALL: [Self; 6] = [
+        Self::SteerLeft,
+        Self::SteerRight,
+        Self::Boost,
+        Self::Pause,
+        Self::Confirm,
+        Self::Mute,
+    ];
+
+    /// Key of the action's name in the string tables.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Self::SteerLeft => "controls.left",
+            Self::SteerRight => "controls.right",
+            Self::Boost => "controls.boost",
+            Self::Pause => "controls.pause",
+            Self::Confirm => "controls.start",
+            Self::Mute => "controls.mute",
+        }
+    }
+
+    /// Boost is only read while driving and Confirm only in menus, so they
+    /// may share a key. Every other pair is live at the same time.
+    pub fn overlaps(&self, other: Self) -> bool {
+        !matches!(
+            (self, other),
+            (Self::Boost, Self::Confirm) | (Self::Confirm, Self::Boost)
+        )
+    }
+}
+
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyBindings(pub BTreeMap<InputAction, Vec<KeyCode>>);
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self(BTreeMap::from([
+            (InputAction::SteerLeft, vec![KeyCode::Left, KeyCode::A]),
+            (InputAction::SteerRight, vec![KeyCode::Right, KeyCode::D]),
+            (InputAction::Boost, vec![KeyCode::Space]),
+            (InputAction::Pause, vec![KeyCode::Escape]),
+            (InputAction::Confirm, vec![KeyCode::Space, KeyCode::Return]),
+            (InputAction::Mute, vec![KeyCode::M]),
+        ]))
+    }
+}
+
+impl KeyBindings {
+    /// Reads the bindings file, falling back to the defaults for a missing
+    /// file and for actions it does not mention.
+    pub fn load(path: &Path) -> Self {
+        let mut bindings = Self::default();
+        if let Some(saved) = read_ron::<Self>(path) {
+            bindings.0.extend(saved.0);
+        }
+
+        bindings
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        write_ron(path, self)
+    }
+
+    /// The bindings from the user's config file.
+    pub fn load_user() -> Self {
+        config_path(CONTROLS_FILE)
+            .map(|path| Self::load(&path))
+            .unwrap_or_default()
+    }
+
+    pub fn save_user(&self) {
+        let Some(path) = config_path(CONTROLS_FILE) else {
+            return;
+        };
+
+        if let Err(err) = self.save(&path) {
+            error!("Could not save {}: {}", path.display(), err);
+        }
+    }
+
+    pub fn keys(&self, action: InputAction) -> &[KeyCode] {
+        self.0.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn pressed(&self, input: &Input<KeyCode>, action: InputAction) -> bool {
+        input.any_pressed(self.keys(action).iter().copied())
+    }
+
+    pub fn just_pressed(&self, input: &Input<KeyCode>, action: InputAction) -> bool {
+        input.any_just_pressed(self.keys(action).iter().copied())
+    }
+
+    pub fn just_released(&self, input: &Input<KeyCode>, action: InputAction) -> bool {
+        input.any_just_released(self.keys(action).iter().copied())
+    }
+
+    /// Like `just_pressed`, but consumes the press.
+    pub fn clear_just_pressed(&self, input: &mut Input<KeyCode>, action: InputAction) -> bool {
+        let mut pressed = false;
+        for &key in self.keys(action) {
+            pressed |= input.clear_just_pressed(key);
+        }
+
+        pressed
+    }
+
+    /// The action already using `key` that would clash with `action`.
+    pub fn conflict(&self, action: InputAction, key: KeyCode) -> Option<InputAction> {
+        InputAction::ALL.into_iter().find(|&other| {
+            other != action && action.overlaps(other) && self.keys(other).contains(&key)
+        })
+    }
+
+    pub fn rebind(&mut self, action: InputAction, key: KeyCode) -> Result<(), InputAction> {
+        if let Some(other) = self.conflict(action, key) {
+            return Err(other);
+        }
+
+        self.0.insert(action, vec![key]);
+        Ok(())
+    }
+}
+
+#[derive(Component)]
+pub struct ControlsRow(pub usize);
+
+#[derive(Resource)]
+pub struct ControlsScreen {
+    pub root: Entity,
+    pub status: Entity,
+    pub selected: usize,
+    /// Counts down to giving up while waiting for a key to bind.
+    pub listening: Option<Timer>,
+    pub message: String,
+}
+
+fn key_names(keys: &[KeyCode]) -> String {
+    keys.iter()
+        .map(|key| format!("{:?}", key))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub fn setup_controls(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    theme: Res<Theme>,
+    strings: Res<Strings>,
+) {
+    let text_style = TextStyle {
+        font: asset_server.load("fonts/Calculator.ttf"),
+        font_size: 20.0,
+        ..default()
+    };
+
+    let mut status = None;
+    let root = commands
+        .spawn(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(Themed::Background)
+        .with_children(|parent| {
+            for (index, _) in InputAction::ALL.iter().enumerate() {
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                size: Size::new(Val::Px(280.0), Val::Px(36.0)),
+                                margin: UiRect::all(Val::Px(4.0)),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            background_color: theme.button.into(),
+                            ..default()
+                        },
+                        ControlsRow(index),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            TextBundle::from_section("", text_style.clone()),
+                            Themed::ButtonText,
+                        ));
+                    });
+            }
+
+            status = Some(
+                parent
+                    .spawn((
+                        TextBundle::from_section("", text_style.clone()).with_style(Style {
+                            margin: UiRect::all(Val::Px(8.0)),
+                            ..default()
+                        }),
+                        Themed::Text,
+                    ))
+                    .id(),
+            );
+        })
+        .id();
+
+    commands.insert_resource(ControlsScreen {
+        root,
+        status: status.unwrap(),
+        selected: 0,
+        listening: None,
+        message: strings.get("controls.help").to_string(),
+    });
+}
+
+pub fn controls(
+    mut state: ResMut<State<GameState>>,
+    mut screen: ResMut<ControlsScreen>,
+    mut bindings: ResMut<KeyBindings>,
+    strings: Res<Strings>,
+    time: Res<Time>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    interaction_query: Query<(&Interaction, &ControlsRow), Changed<Interaction>>,
+) {
+    let listen = || Some(Timer::new(LISTEN_TIMEOUT, TimerMode::Once));
+
+    for (interaction, row) in &interaction_query {
+        if *interaction == Interaction::Clicked {
+            screen.selected = row.0;
+            screen.listening = listen();
+            screen.message = strings.get("controls.press_key").to_string();
+        }
+    }
+
+    if let Some(timer) = &mut screen.listening {
+        let Some(&key) = keyboard_input.get_just_pressed().next() else {
+            if timer.tick(time.delta()).finished() {
+                screen.listening = None;
+                screen.message = strings.get("controls.unchanged").to_string();
+            }
+            return;
+        };
+        keyboard_input.clear();
+        screen.listening = None;
+
+        let action = InputAction::ALL[screen.selected];
+        screen.message = match bindings.rebind(action, key) {
+            Ok(()) => {
+                bindings.save_user();
+                String::new()
+            }
+            Err(other) => strings.format(
+                "controls.taken",
+                &[&format!("{:?}", key), &strings.get(other.key())],
+            ),
+        };
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Up) {
+        screen.selected = screen.selected.saturating_sub(1);
+    }
+    if keyboard_input.just_pressed(KeyCode::Down) {
+        screen.selected = (screen.selected + 1).min(InputAction::ALL.len() - 1);
+    }
+    if keyboard_input.clear_just_pressed(KeyCode::Return) {
+        screen.listening = listen();
+        screen.message = strings.get("controls.press_key").to_string();
+    }
+    if keyboard_input.just_pressed(KeyCode::Delete) {
+        *bindings = KeyBindings::default();
+        bindings.save_user();
+    }
+    if keyboard_input.clear_just_pressed(KeyCode::Escape) {
+        state.set(GameState::Settings).unwrap();
+    }
+}
+
+pub fn update_controls(
+    screen: Res<ControlsScreen>,
+    bindings: Res<KeyBindings>,
+    theme: Res<Theme>,
+    strings: Res<Strings>,
+    mut rows_query: Query<(&ControlsRow, &Children, &mut BackgroundColor)>,
+    mut text_query: Query<&mut Text>,
+) {
+    for (row, children, mut color) in &mut rows_query {
+        let action = InputAction::ALL[row.0];
+        let keys = if screen.listening.is_some() && row.0 == screen.selected {
+            "...".to_string()
+        } else {
+            key_names(bindings.keys(action))
+        };
+
+        *color = if row.0 == screen.selected {
+            theme.button_hovered.into()
+        } else {
+            theme.button.into()
+        };
+
+        if let Ok(mut text) = text_query.get_mut(children[0]) {
+            text.sections[0].value = format!("{}  {}", strings.get(action.key()), keys);
+        }
+    }
+
+    if let Ok(mut text) = text_query.get_mut(screen.status) {
+        text.sections[0].value = screen.message.clone();
+    }
+}
+
+pub fn cleanup_controls(mut commands: Commands, screen: Res<ControlsScreen>) {
+    commands.entity(screen.root).despawn_recursive();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebinding_refuses_keys_in_use() {
+        let mut bindings = KeyBindings::default();
+
+        assert_eq!(
+            bindings.rebind(InputAction::Mute, KeyCode::A),
+            Err(InputAction::SteerLeft)
+        );
+        assert_eq!(bindings.keys(InputAction::Mute), [KeyCode::M]);
+
+        assert_eq!(bindings.rebind(InputAction::Mute, KeyCode::N), Ok(()));
+        assert_eq!(bindings.keys(InputAction::Mute), [KeyCode::N]);
+        assert_eq!(bindings.conflict(InputAction::Pause, KeyCode::M), None);
+    }
+
+    #[test]
+    fn boost_and_confirm_may_share_a_key() {
+        let mut bindings = KeyBindings::default();
+
+        assert_eq!(
+            bindings.conflict(InputAction::Confirm, KeyCode::Space),
+            None
+        );
+        assert_eq!(bindings.rebind(InputAction::Boost, KeyCode::Return), Ok(()));
+        assert_eq!(
+            bindings.rebind(InputAction::Pause, KeyCode::Return),
+            Err(InputAction::Boost)
+        );
+    }
+
+    #[test]
+    fn an_action_can_take_back_its_own_key() {
+        let mut bindings = KeyBindings::default();
+
+        assert_eq!(bindings.rebind(InputAction::Pause, KeyCode::P), Ok(()));
+        assert_eq!(bindings.rebind(InputAction::Pause, KeyCode::Escape), Ok(()));
+        assert_eq!(bindings.keys(InputAction::Pause), [KeyCode::Escape]);
+    }
+
+    #[test]
+    fn saved_bindings_keep_defaults_for_the_rest() {
+        let path = std::env::temp_dir().join("bevy_retro_racing_controls_test.ron");
+        std::fs::write(&path, "({Mute: [N]})").unwrap();
+
+        let bindings = KeyBindings::load(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(bindings.keys(InputAction::Mute), [KeyCode::N]);
+        assert_eq!(
+            bindings.keys(InputAction::SteerLeft),
+            [KeyCode::Left, KeyCode::A]
+        );
+    }
+}
@@ -0,0 +1,153 @@
+use crate::prelude::*;
+use bevy::input::touch::Touches;
+
+pub const DEMO_IDLE_SECONDS: f32 = 5.0;
+
+/// Attract mode: after the menu sits idle for a while a bot plays a run
+/// behind it, until any key or button is pressed.
+#[derive(Resource)]
+pub struct AttractMode {
+    pub idle: Timer,
+    pub bot: Bot,
+    /// The run the menu had waiting, and whether it was being recorded,
+    /// put back untouched when the demo ends.
+    waiting: Option<(RaceConfig, bool)>,
+}
+
+impl Default for AttractMode {
+    fn default() -> Self {
+        Self {
+            idle: Timer::from_seconds(DEMO_IDLE_SECONDS, TimerMode::Once),
+            bot: Bot::new(Policy::Perfect, thread_rng().gen()),
+            waiting: None,
+        }
+    }
+}
+
+impl AttractMode {
+    /// Swaps the waiting run, which may be a `--seed` the player asked for,
+    /// for a demo run on a seed of its own.
+    pub fn begin(&mut self, race: &mut Race) {
+        self.waiting = Some((race.config, race.replay.is_some()));
+        *race = Race::new(RaceConfig {
+            seed: thread_rng().gen(),
+            ..race.config
+        });
+    }
+
+    /// Puts the waiting run back, fresh and with its own seed.
+    pub fn end(&mut self, race: &mut Race) {
+        let Some((config, recording)) = self.waiting.take() else {
+            race.restart();
+            return;
+        };
+
+        *race = Race::new(config);
+        if recording {
+            race.record();
+        }
+    }
+}
+
+fn any_input(
+    keyboard_input: &Input<KeyCode>,
+    mouse_input: &Input<MouseButton>,
+    gamepad_input: &Input<GamepadButton>,
+    touches: &Touches,
+) -> bool {
+    keyboard_input.get_just_pressed().next().is_some()
+        || mouse_input.get_just_pressed().next().is_some()
+        || gamepad_input.get_just_pressed().next().is_some()
+        || touches.any_just_pressed()
+}
+
+pub fn reset_idle(mut attract: ResMut<AttractMode>) {
+    attract.idle.reset();
+}
+
+pub fn start_demo(
+    mut state: ResMut<State<GameState>>,
+    mut attract: ResMut<AttractMode>,
+    mut race: ResMut<Race>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    touches: Res<Touches>,
+    timer: Res<Time>,
+) {
+    if any_input(&keyboard_input, &mouse_input, &gamepad_input, &touches) {
+        attract.idle.reset();
+        return;
+    }
+
+    if attract.idle.tick(timer.delta()).just_finished() {
+        // Pushing keeps the menu alive underneath the demo run.
+        state.push(GameState::Demo).unwrap();
+        attract.begin(&mut race);
+    }
+}
+
+pub fn autopilot(mut attract: ResMut<AttractMode>, mut race: ResMut<Race>, timer: Res<Time>) {
+    let steer = attract.bot.decide(&race, timer.delta());
+    race.steer(steer);
+}
+
+pub fn stop_demo(
+    mut state: ResMut<State<GameState>>,
+    race: Res<Race>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut mouse_input: ResMut<Input<MouseButton>>,
+    mut gamepad_input: ResMut<Input<GamepadButton>>,
+    touches: Res<Touches>,
+) {
+    if race.is_over() || any_input(&keyboard_input, &mouse_input, &gamepad_input, &touches) {
+        // The press only stops the demo, the menu must not act on it too.
+        keyboard_input.clear();
+        mouse_input.clear();
+        gamepad_input.clear();
+        state.pop().unwrap();
+    }
+}
+
+pub fn cleanup_demo(
+    mut commands: Commands,
+    cars_query: Query<Entity, With<Car>>,
+    mut attract: ResMut<AttractMode>,
+    mut race: ResMut<Race>,
+) {
+    for entity in &cars_query {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    attract.end(&mut race);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn a_demo_leaves_the_seeded_run_waiting() {
+        let config = RaceConfig {
+            lanes: 4,
+            level: 10,
+            seed: 42,
+        };
+        let mut race = Race::new(config);
+        race.record();
+        let mut attract = AttractMode::default();
+
+        attract.begin(&mut race);
+        assert_eq!(race.config.lanes, 4);
+        while !race.is_over() && race.elapsed < Duration::from_secs(5) {
+            race.update(Duration::from_millis(16));
+        }
+        attract.end(&mut race);
+
+        assert_eq!(race.config.seed, 42);
+        assert_eq!(race.elapsed, Duration::ZERO);
+        assert!(race.replay.is_some());
+        assert_eq!(race.rivals, Race::new(config).rivals);
+    }
+}
@@ -0,0 +1,110 @@
+use crate::prelude::*;
+use std::time::Duration;
+
+/// Time constant of the engine's response: it covers about two thirds of
+/// the way to a new pitch or volume in this long.
+pub const ENGINE_SMOOTHING: Duration = Duration::from_millis(150);
+/// Extra pitch while boosting, on top of the pitch for the current speed.
+pub const BOOST_PITCH: f32 = 1.4;
+/// Share of the full engine volume when not boosting.
+pub const CRUISE_VOLUME: f32 = 0.75;
+
+/// Smoothed playback speed and volume of the looping engine sound.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EngineSound {
+    pub speed: f32,
+    pub volume: f32,
+}
+
+impl EngineSound {
+    pub fn new(race: &Race) -> Self {
+        let (speed, volume) = Self::target(race);
+        Self { speed, volume }
+    }
+
+    /// Where the engine is heading: higher as the run speeds up, revving
+    /// while boosting.
+    pub fn target(race: &Race) -> (f32, f32) {
+        let speed = race.speed_factor.sqrt();
+
+        if race.is_boosting {
+            (speed * BOOST_PITCH, 1.0)
+        } else {
+            (speed, CRUISE_VOLUME)
+        }
+    }
+
+    pub fn update(&mut self, race: &Race, delta: Duration) {
+        let (speed, volume) = Self::target(race);
+        let blend = 1.0 - (-delta.as_secs_f32() / ENGINE_SMOOTHING.as_secs_f32()).exp();
+
+        self.speed += (speed - self.speed) * blend;
+        self.volume += (volume - self.volume) * blend;
+    }
+}
+
+pub fn rev_engine(
+    race: Res<Race>,
+    settings: Res<Settings>,
+    mute: Res<MuteOverride>,
+    timer: Res<Time>,
+    audio_sinks: Res<Assets<AudioSink>>,
+    motor_controller: Option<ResMut<MotorController>>,
+    player: Query<&Car, With<Player>>,
+) {
+    let Some(mut motor) = motor_controller else {
+        return;
+    };
+
+    motor.engine.update(&race, timer.delta());
+    motor.pan.set(player_pan(&race, &player));
+
+    if let Some(sink) = audio_sinks.get(&motor.sink) {
+        sink.set_speed(motor.engine.speed);
+        sink.set_volume(
+            MOTOR_VOLUME * settings.mixer.gain(Bus::Engine, *mute) * motor.engine.volume,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: Duration = Duration::from_millis(16);
+
+    #[test]
+    fn boosting_revs_up_smoothly_and_back() {
+        let mut race = Race::default();
+        let mut engine = EngineSound::new(&race);
+        let cruise = engine;
+
+        race.is_boosting = true;
+        engine.update(&race, FRAME);
+        assert!(engine.speed > cruise.speed && engine.speed < cruise.speed * BOOST_PITCH);
+        assert!(engine.volume > cruise.volume && engine.volume < 1.0);
+
+        for _ in 0..120 {
+            engine.update(&race, FRAME);
+        }
+        assert!((engine.speed - cruise.speed * BOOST_PITCH).abs() < 1e-3);
+
+        race.is_boosting = false;
+        for _ in 0..120 {
+            engine.update(&race, FRAME);
+        }
+        assert!((engine.speed - cruise.speed).abs() < 1e-3);
+        assert!((engine.volume - CRUISE_VOLUME).abs() < 1e-3);
+    }
+
+    #[test]
+    fn pitch_rises_with_the_level() {
+        let slow = EngineSound::new(&Race::default());
+        let fast = EngineSound::new(&Race::new(RaceConfig {
+            level: MAX_LEVEL,
+            ..default()
+        }));
+
+        assert!(fast.speed > slow.speed);
+    }
+}
@@ -3,14 +3,13 @@ pub use crate::prelude::*;
 #[derive(Component)]
 pub struct Player;
 
+/// Index of the enemy in `Race::rivals`.
 #[derive(Component)]
-pub struct Enemy;
+pub struct Enemy(pub usize);
 
+/// Index of the wall in `Race::walls`.
 #[derive(Component)]
-pub struct Wall;
-
-#[derive(Component)]
-pub struct MoveY;
+pub struct Wall(pub usize);
 
 #[derive(Component)]
 pub struct Car {
@@ -0,0 +1,143 @@
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+pub const ENV_FPS: u32 = 60;
+pub const CRASH_REWARD: f32 = -10.0;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    #[default]
+    None,
+    Left,
+    Right,
+    Boost,
+}
+
+/// The playfield from the top row down, one `0`/`1` per tile.
+pub type Observation = Vec<Vec<u8>>;
+
+/// Step-style wrapper around `Race` for training agents. Every step holds the
+/// action for `frame_skip` frames of `1 / ENV_FPS` seconds; the reward is the
+/// number of rows driven, or `CRASH_REWARD` on a crash.
+pub struct RaceEnv {
+    pub race: Race,
+    pub frame_skip: u32,
+}
+
+impl RaceEnv {
+    pub fn new(config: RaceConfig, frame_skip: u32) -> Self {
+        Self {
+            race: Race::new(config),
+            frame_skip: frame_skip.max(1),
+        }
+    }
+
+    pub fn observation(&self) -> Observation {
+        TileGrid::from_race(&self.race)
+            .rows()
+            .map(|row| row.iter().map(|&lit| lit as u8).collect())
+            .collect()
+    }
+
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.race = Race::new(RaceConfig {
+            seed,
+            ..self.race.config
+        });
+        self.observation()
+    }
+
+    pub fn step(&mut self, action: Action) -> (Observation, f32, bool) {
+        let delta = Duration::from_secs(1) / ENV_FPS;
+        let distance = self.race.distance;
+
+        self.race.is_boosting = action == Action::Boost;
+        match action {
+            Action::Left => self.race.steer(Steer::Left),
+            Action::Right => self.race.steer(Steer::Right),
+            _ => false,
+        };
+
+        for _ in 0..self.frame_skip {
+            if self.race.is_over() {
+                break;
+            }
+            self.race.update(delta);
+        }
+
+        let done = self.race.is_over();
+        let reward = if done {
+            CRASH_REWARD
+        } else {
+            (self.race.distance - distance) as f32
+        };
+
+        (self.observation(), reward, done)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env() -> RaceEnv {
+        RaceEnv::new(
+            RaceConfig {
+                lanes: LANES,
+                level: 0,
+                seed: 0,
+            },
+            4,
+        )
+    }
+
+    #[test]
+    fn steps_report_rows_driven_until_a_crash() {
+        let mut env = env();
+        let observation = env.reset(5);
+        assert_eq!(observation.len(), SCREEN_HEIGHT);
+        assert_eq!(observation[0].len(), TileScreen::screen_width(LANES));
+        assert_eq!(env.race.config.seed, 5);
+
+        for rival in &mut env.race.rivals {
+            rival.row = 1000;
+        }
+        let mut driven = 0.0;
+        for _ in 0..10 {
+            let (_, reward, done) = env.step(Action::None);
+            assert!(!done);
+            driven += reward;
+        }
+        assert_eq!(driven, env.race.distance as f32);
+        assert!(driven > 0.0);
+
+        // A car right ahead in the player's lane.
+        env.race.rivals[0] = Rival {
+            lane: env.race.lane,
+            row: *HIT_ROWS.end() + 1,
+        };
+        let (observation, reward, done) = env.step(Action::Boost);
+        assert!(done);
+        assert_eq!(reward, CRASH_REWARD);
+        assert_eq!(observation, env.observation());
+        assert!(env.race.crash.is_some_and(|crash| crash.boosting));
+    }
+
+    #[test]
+    fn steering_actions_change_lane() {
+        let mut env = env();
+        env.reset(1);
+        for rival in &mut env.race.rivals {
+            rival.row = 1000;
+        }
+        let lane = env.race.lane;
+
+        env.step(Action::Left);
+        assert_eq!(env.race.lane, lane - 1);
+        env.step(Action::Right);
+        env.step(Action::Right);
+        assert_eq!(env.race.lane, lane + 1);
+    }
+}
@@ -0,0 +1,236 @@
+use crate::prelude::*;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+/// Frames per second of an export. GIF delays count hundredths of a second,
+/// so this divides 100 evenly.
+pub const EXPORT_FPS: u32 = 20;
+/// How long the last frame, usually the crash, stays up.
+pub const FINAL_HOLD: Duration = Duration::from_secs(1);
+
+/// The tile grid every `1 / EXPORT_FPS` seconds of a replay, from the start
+/// through `FINAL_HOLD` past the last recorded frame.
+pub fn capture(replay: &Replay) -> Vec<TileGrid> {
+    let step = Duration::from_secs(1) / EXPORT_FPS;
+    let mut race = Race::new(replay.config);
+    let mut grids = vec![TileGrid::from_race(&race)];
+    let mut next = step;
+
+    for frame in &replay.frames {
+        Replay::apply(frame, &mut race);
+        while race.elapsed >= next {
+            grids.push(TileGrid::from_race(&race));
+            next += step;
+        }
+    }
+
+    let last = TileGrid::from_race(&race);
+    let hold = (FINAL_HOLD.as_secs_f32() * EXPORT_FPS as f32) as usize;
+    grids.extend(std::iter::repeat_n(last, hold));
+
+    grids
+}
+
+/// A tile layer pixel blended onto the opaque theme background.
+fn flatten(pixel: &[u8], background: Vec4) -> [u8; BYTES_PER_PIXEL] {
+    let alpha = pixel[3] as f32 / 255.0;
+    let color = Vec4::new(pixel[0] as f32, pixel[1] as f32, pixel[2] as f32, 255.0) / 255.0;
+    let [r, g, b, _] = background.lerp(color, alpha).to_array();
+
+    rgba8(Color::rgb(r, g, b))
+}
+
+/// Every color an exported frame is made of: the gaps between cells, an
+/// unlit cell and a lit one.
+pub fn frame_palette(theme: &Theme) -> [[u8; BYTES_PER_PIXEL]; 3] {
+    let background = Vec4::from(theme.background.as_rgba_f32());
+    [
+        [0; BYTES_PER_PIXEL],
+        rgba8(theme.lit(0.0)),
+        rgba8(theme.lit(1.0)),
+    ]
+    .map(|pixel| flatten(&pixel, background))
+}
+
+/// One frame as opaque RGBA pixels, `scale` pixels for every pixel of the
+/// in-game tile layer.
+pub fn render_frame(grid: &TileGrid, theme: &Theme, scale: usize) -> Vec<u8> {
+    let (width, height) = (grid.width * TILE_PIXELS, grid.height * TILE_PIXELS);
+    let mut tiles = vec![0; width * height * BYTES_PER_PIXEL];
+    rasterize(&mut tiles, grid.width, grid.height, theme, |x, y| {
+        grid.get(x, y) as u8 as f32
+    });
+
+    let background = Vec4::from(theme.background.as_rgba_f32());
+    let mut pixels = Vec::with_capacity(tiles.len() * scale * scale);
+    for row in tiles.chunks_exact(width * BYTES_PER_PIXEL) {
+        let mut scaled = Vec::with_capacity(row.len() * scale);
+        for pixel in row.chunks_exact(BYTES_PER_PIXEL) {
+            let opaque = flatten(pixel, background);
+            for _ in 0..scale {
+                scaled.extend_from_slice(&opaque);
+            }
+        }
+        for _ in 0..scale {
+            pixels.extend_from_slice(&scaled);
+        }
+    }
+
+    pixels
+}
+
+/// Writes frames from `render_frame` as a looping GIF on the theme's
+/// palette, one at a time so long runs fit in memory. Returns the number
+/// of frames.
+pub fn write_gif(
+    writer: impl Write,
+    frames: impl IntoIterator<Item = Vec<u8>>,
+    width: usize,
+    height: usize,
+    theme: &Theme,
+) -> io::Result<usize> {
+    let palette = frame_palette(theme);
+    let gif_palette: Vec<u8> = palette
+        .iter()
+        .flat_map(|color| &color[..3])
+        .copied()
+        .collect();
+
+    let gif_error = |err: gif::EncodingError| io::Error::other(err);
+    let mut encoder =
+        gif::Encoder::new(writer, width as u16, height as u16, &gif_palette).map_err(gif_error)?;
+    encoder
+        .set_repeat(gif::Repeat::Infinite)
+        .map_err(gif_error)?;
+
+    let mut count = 0;
+    for pixels in frames {
+        let indices = pixels
+            .chunks_exact(BYTES_PER_PIXEL)
+            .map(|pixel| palette.iter().position(|color| color == pixel))
+            .map(|index| index.map(|index| index as u8))
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "frame has colors off the theme")
+            })?;
+
+        let mut frame =
+            gif::Frame::from_indexed_pixels(width as u16, height as u16, &indices, None);
+        frame.delay = (100 / EXPORT_FPS) as u16;
+        encoder.write_frame(&frame).map_err(gif_error)?;
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+/// Writes frames into `dir` as `frame_0000.png`, `frame_0001.png`, ...
+/// Returns the number of frames.
+pub fn write_png_frames(
+    dir: &Path,
+    frames: impl IntoIterator<Item = Vec<u8>>,
+    width: usize,
+    height: usize,
+) -> io::Result<usize> {
+    fs::create_dir_all(dir)?;
+
+    let mut count = 0;
+    for pixels in frames {
+        let file = File::create(dir.join(format!("frame_{count:04}.png")))?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), width as u32, height as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&pixels)?;
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+/// Renders a replay to `path`: an animated GIF when it ends in `.gif`,
+/// otherwise a directory of PNG frames. Returns the number of frames.
+pub fn export_replay(
+    replay: &Replay,
+    theme: &Theme,
+    scale: usize,
+    path: &Path,
+) -> io::Result<usize> {
+    let frames = capture(replay)
+        .into_iter()
+        .map(|grid| render_frame(&grid, theme, scale));
+    let width = TileScreen::screen_width(replay.config.lanes) * TILE_PIXELS * scale;
+    let height = SCREEN_HEIGHT * TILE_PIXELS * scale;
+
+    let is_gif = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("gif"));
+    if is_gif {
+        let file = BufWriter::new(File::create(path)?);
+        write_gif(file, frames, width, height, theme)
+    } else {
+        write_png_frames(path, frames, width, height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn short_run() -> Replay {
+        let mut race = Race::new(RaceConfig {
+            lanes: LANES,
+            level: 0,
+            seed: 3,
+        });
+        race.record();
+        for _ in 0..30 {
+            race.update(Duration::from_millis(20));
+        }
+
+        race.replay.unwrap()
+    }
+
+    #[test]
+    fn capture_samples_at_the_export_rate_and_holds_the_end() {
+        let replay = short_run();
+        let grids = capture(&replay);
+
+        // 600ms at 20fps after the starting frame, then the hold.
+        assert_eq!(grids.len(), 1 + 12 + EXPORT_FPS as usize);
+        assert_eq!(grids.last(), grids.get(grids.len() - 2));
+    }
+
+    #[test]
+    fn gif_round_trips_through_a_decoder() {
+        let theme = Theme::preset(ThemePreset::Dmg);
+        let grid = TileGrid::from_race(&Race::default());
+        let frames = vec![
+            render_frame(&grid, &theme, 1),
+            render_frame(&grid, &theme, 1),
+        ];
+        let (width, height) = (grid.width * TILE_PIXELS, grid.height * TILE_PIXELS);
+
+        let mut bytes = Vec::new();
+        let written = write_gif(&mut bytes, frames.clone(), width, height, &theme).unwrap();
+        assert_eq!(written, 2);
+
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::RGBA);
+        let mut decoder = options.read_info(bytes.as_slice()).unwrap();
+        let mut decoded = 0;
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            assert_eq!(frame.delay, 5);
+            assert_eq!(*frame.buffer, *frames[decoded]);
+            decoded += 1;
+        }
+        assert_eq!(decoded, 2);
+
+        // The gap in the corner shows the background, the car's tiles are lit.
+        assert_eq!(frames[0][..4], rgba8(theme.background));
+        assert!(frames[0]
+            .chunks_exact(4)
+            .any(|pixel| pixel == rgba8(theme.tile)));
+    }
+}
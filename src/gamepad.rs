@@ -0,0 +1,280 @@
+use crate::prelude::*;
+use std::collections::HashMap;
+
+/// Stick travel needed to change lanes, and how far it has to come back
+/// towards the centre before it can change lanes again.
+pub const STICK_PRESS: f32 = 0.6;
+pub const STICK_RELEASE: f32 = 0.3;
+
+pub fn gamepad_just_pressed(
+    gamepads: &Gamepads,
+    buttons: &Input<GamepadButton>,
+    button_type: GamepadButtonType,
+) -> bool {
+    gamepads
+        .iter()
+        .any(|gamepad| buttons.just_pressed(GamepadButton::new(gamepad, button_type)))
+}
+
+pub fn gamepad_just_released(
+    gamepads: &Gamepads,
+    buttons: &Input<GamepadButton>,
+    button_type: GamepadButtonType,
+) -> bool {
+    gamepads
+        .iter()
+        .any(|gamepad| buttons.just_released(GamepadButton::new(gamepad, button_type)))
+}
+
+/// Like `gamepad_just_pressed`, but consumes the press on every pad.
+pub fn gamepad_clear_just_pressed(
+    gamepads: &Gamepads,
+    buttons: &mut Input<GamepadButton>,
+    button_type: GamepadButtonType,
+) -> bool {
+    let mut pressed = false;
+    for gamepad in gamepads.iter() {
+        pressed |= buttons.clear_just_pressed(GamepadButton::new(gamepad, button_type));
+    }
+
+    pressed
+}
+
+/// Turns a stick axis into one steer per push: `latched` remembers the last
+/// push until the stick is back inside the release zone.
+pub fn stick_steer(latched: &mut Steer, x: f32) -> Steer {
+    match *latched {
+        Steer::Straight if x <= -STICK_PRESS => *latched = Steer::Left,
+        Steer::Straight if x >= STICK_PRESS => *latched = Steer::Right,
+        Steer::Straight => {}
+        _ if x.abs() < STICK_RELEASE => *latched = Steer::Straight,
+        _ => return Steer::Straight,
+    }
+
+    *latched
+}
+
+/// Lane changes from every connected gamepad's D-pad and left stick.
+pub fn gamepad_steers(
+    gamepads: &Gamepads,
+    buttons: &Input<GamepadButton>,
+    axes: &Axis<GamepadAxis>,
+    sticks: &mut HashMap<Gamepad, Steer>,
+) -> Vec<Steer> {
+    let mut steers = Vec::new();
+
+    for gamepad in gamepads.iter() {
+        if buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::DPadLeft)) {
+            steers.push(Steer::Left);
+        }
+        if buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::DPadRight)) {
+            steers.push(Steer::Right);
+        }
+
+        let x = axes
+            .get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX))
+            .unwrap_or_default();
+        let steer = stick_steer(sticks.entry(gamepad).or_default(), x);
+        if steer != Steer::Straight {
+            steers.push(steer);
+        }
+    }
+
+    steers
+}
+
+/// Directions held on any gamepad, from the D-pad or a latched stick.
+pub fn gamepad_held(
+    gamepads: &Gamepads,
+    buttons: &Input<GamepadButton>,
+    sticks: &HashMap<Gamepad, Steer>,
+) -> Vec<Steer> {
+    let mut held = Vec::new();
+
+    for gamepad in gamepads.iter() {
+        if buttons.pressed(GamepadButton::new(gamepad, GamepadButtonType::DPadLeft)) {
+            held.push(Steer::Left);
+        }
+        if buttons.pressed(GamepadButton::new(gamepad, GamepadButtonType::DPadRight)) {
+            held.push(Steer::Right);
+        }
+
+        match sticks.get(&gamepad) {
+            Some(&steer) if steer != Steer::Straight => held.push(steer),
+            _ => {}
+        }
+    }
+
+    held
+}
+
+pub fn gamepad_player(
+    gamepads: Res<Gamepads>,
+    buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    mut sticks: Local<HashMap<Gamepad, Steer>>,
+    mut steering: ResMut<SteerQueue>,
+    mut race: ResMut<Race>,
+) {
+    for steer in gamepad_steers(&gamepads, &buttons, &axes, &mut sticks) {
+        steering.press(steer);
+    }
+
+    for steer in gamepad_held(&gamepads, &buttons, &sticks) {
+        steering.hold(steer);
+    }
+
+    if gamepad_just_pressed(&gamepads, &buttons, GamepadButtonType::South) {
+        race.is_boosting = true;
+    }
+
+    if gamepad_just_released(&gamepads, &buttons, GamepadButtonType::South) {
+        race.is_boosting = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::input::gamepad::{GamepadEventRaw, GamepadEventType, GamepadInfo};
+    use bevy::input::InputPlugin;
+
+    const PAD: Gamepad = Gamepad { id: 0 };
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(InputPlugin)
+            .insert_resource(Race::new(RaceConfig {
+                lanes: 3,
+                level: 0,
+                seed: 1,
+            }))
+            .init_resource::<KeyBindings>()
+            .init_resource::<SteerQueue>()
+            .add_event::<Sfx>()
+            .add_system(gamepad_player)
+            .add_system(steer_player.after(gamepad_player));
+        send(
+            &mut app,
+            GamepadEventType::Connected(GamepadInfo {
+                name: "Test pad".to_string(),
+            }),
+        );
+        app
+    }
+
+    fn send(app: &mut App, event_type: GamepadEventType) {
+        app.world.send_event(GamepadEventRaw::new(PAD, event_type));
+        app.update();
+    }
+
+    fn lane(app: &App) -> usize {
+        app.world.resource::<Race>().lane
+    }
+
+    #[test]
+    fn stick_steers_once_per_push() {
+        let mut latched = Steer::Straight;
+
+        assert_eq!(stick_steer(&mut latched, 0.2), Steer::Straight);
+        assert_eq!(stick_steer(&mut latched, -0.9), Steer::Left);
+        assert_eq!(stick_steer(&mut latched, -1.0), Steer::Straight);
+        assert_eq!(stick_steer(&mut latched, -0.4), Steer::Straight);
+        assert_eq!(stick_steer(&mut latched, -0.1), Steer::Straight);
+        assert_eq!(stick_steer(&mut latched, 0.7), Steer::Right);
+    }
+
+    #[test]
+    fn dpad_changes_lanes() {
+        let mut app = app();
+
+        send(
+            &mut app,
+            GamepadEventType::ButtonChanged(GamepadButtonType::DPadLeft, 1.0),
+        );
+        assert_eq!(lane(&app), 0);
+
+        send(
+            &mut app,
+            GamepadEventType::ButtonChanged(GamepadButtonType::DPadLeft, 0.0),
+        );
+        send(
+            &mut app,
+            GamepadEventType::ButtonChanged(GamepadButtonType::DPadRight, 1.0),
+        );
+        assert_eq!(lane(&app), 1);
+    }
+
+    #[test]
+    fn stick_ignores_dead_zone_and_held_pushes() {
+        let mut app = app();
+
+        send(
+            &mut app,
+            GamepadEventType::AxisChanged(GamepadAxisType::LeftStickX, 0.4),
+        );
+        assert_eq!(lane(&app), 1);
+
+        send(
+            &mut app,
+            GamepadEventType::AxisChanged(GamepadAxisType::LeftStickX, 0.9),
+        );
+        assert_eq!(lane(&app), 2);
+
+        app.update();
+        assert_eq!(lane(&app), 2);
+
+        send(
+            &mut app,
+            GamepadEventType::AxisChanged(GamepadAxisType::LeftStickX, 0.0),
+        );
+        send(
+            &mut app,
+            GamepadEventType::AxisChanged(GamepadAxisType::LeftStickX, -0.9),
+        );
+        assert_eq!(lane(&app), 1);
+    }
+
+    #[test]
+    fn start_toggles_pause() {
+        let mut app = app();
+        app.add_state(GameState::Playing)
+            .add_system_set(SystemSet::on_update(GameState::Playing).with_system(pause_game))
+            .add_system_set(SystemSet::on_update(GameState::Paused).with_system(resume_game));
+        let state = |app: &App| app.world.resource::<State<GameState>>().current().clone();
+
+        send(
+            &mut app,
+            GamepadEventType::ButtonChanged(GamepadButtonType::Start, 1.0),
+        );
+        assert_eq!(state(&app), GameState::Paused);
+
+        send(
+            &mut app,
+            GamepadEventType::ButtonChanged(GamepadButtonType::Start, 0.0),
+        );
+        send(
+            &mut app,
+            GamepadEventType::ButtonChanged(GamepadButtonType::Start, 1.0),
+        );
+        assert_eq!(state(&app), GameState::Playing);
+    }
+
+    #[test]
+    fn face_button_boosts_while_held() {
+        let mut app = app();
+
+        send(
+            &mut app,
+            GamepadEventType::ButtonChanged(GamepadButtonType::South, 1.0),
+        );
+        assert!(app.world.resource::<Race>().is_boosting);
+
+        send(
+            &mut app,
+            GamepadEventType::ButtonChanged(GamepadButtonType::South, 0.0),
+        );
+        assert!(!app.world.resource::<Race>().is_boosting);
+    }
+}
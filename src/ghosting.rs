@@ -0,0 +1,74 @@
+use crate::prelude::*;
+use std::time::Duration;
+
+/// How long a cell takes to fade out after its tile moves on.
+pub const GHOST_FADE: Duration = Duration::from_millis(150);
+
+/// How lit each playfield cell is, from 0 to 1, like the slow segments of
+/// the handheld's LCD.
+#[derive(Resource, Debug, Clone, Default, PartialEq)]
+pub struct LcdPersistence {
+    width: usize,
+    height: usize,
+    intensity: Vec<f32>,
+}
+
+impl LcdPersistence {
+    /// Snaps the cells lit in `grid` on and fades the rest.
+    pub fn update(&mut self, grid: &TileGrid, delta: Duration) {
+        if self.width != grid.width || self.height != grid.height {
+            self.width = grid.width;
+            self.height = grid.height;
+            self.intensity = vec![0.0; grid.width * grid.height];
+        }
+
+        let fade = delta.as_secs_f32() / GHOST_FADE.as_secs_f32();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let intensity = &mut self.intensity[y * self.width + x];
+                *intensity = if grid.get(x, y) {
+                    1.0
+                } else {
+                    (*intensity - fade).max(0.0)
+                };
+            }
+        }
+    }
+
+    pub fn intensity(&self, x: usize, y: usize) -> f32 {
+        if x < self.width && y < self.height {
+            self.intensity[y * self.width + x]
+        } else {
+            0.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: Duration = Duration::from_millis(50);
+
+    #[test]
+    fn cells_snap_on_and_fade_over_a_few_frames() {
+        let mut persistence = LcdPersistence::default();
+        let mut grid = TileGrid::new(3, 2);
+        grid.light(1, 1);
+
+        persistence.update(&grid, FRAME);
+        assert_eq!(persistence.intensity(1, 1), 1.0);
+        assert_eq!(persistence.intensity(0, 1), 0.0);
+
+        let dark = TileGrid::new(3, 2);
+        persistence.update(&dark, FRAME);
+        let first = persistence.intensity(1, 1);
+        persistence.update(&dark, FRAME);
+        assert!(first < 1.0 && persistence.intensity(1, 1) < first);
+
+        persistence.update(&dark, FRAME);
+        persistence.update(&dark, FRAME);
+        assert_eq!(persistence.intensity(1, 1), 0.0);
+        assert_eq!(persistence.intensity(5, 5), 0.0);
+    }
+}
@@ -0,0 +1,308 @@
+use crate::prelude::*;
+use bevy::window::{WindowMode, WindowResizeConstraints};
+
+/// Distance from the top of the screen to the first panel entry.
+pub const PANEL_TOP: f32 = WINDOW_PADDING * 3.0;
+/// Space below each score readout.
+pub const PANEL_GAP: f32 = 3.0;
+/// Space between the icons, and above them.
+pub const ICON_GAP: f32 = 10.0;
+
+/// Where the playfield and side panel sit in the window. The screen keeps
+/// its unscaled size in world units and UI pixels; it is magnified by the
+/// largest whole number of physical pixels that fits, so tiles stay crisp
+/// at any DPI, and centred with the clear color filling the bars around it.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct ScreenLayout {
+    /// Physical pixels per world unit and per UI pixel.
+    pub scale: f32,
+    /// The window's logical to physical factor this was fitted for.
+    pub scale_factor: f32,
+    /// Physical position of the screen's top left corner.
+    pub origin: Vec2,
+    /// Unscaled size of the screen.
+    pub size: Vec2,
+    /// World offset that lands the screen's edges on whole pixels when the
+    /// bars around it come out odd.
+    pub camera_nudge: Vec2,
+}
+
+impl ScreenLayout {
+    pub fn screen_size(lanes: usize) -> Vec2 {
+        Vec2::new(TileScreen::window_width(lanes), WINDOW_HEIGHT)
+    }
+
+    /// Fits the screen to a window of `physical` pixels.
+    pub fn fit(physical: Vec2, scale_factor: f32, lanes: usize) -> Self {
+        let size = Self::screen_size(lanes);
+        let scale = (physical / size).min_element().floor().max(1.0);
+        let centred = (physical - size * scale) / 2.0;
+        let origin = centred.round();
+        let nudge = (centred - origin) / scale;
+
+        Self {
+            scale,
+            scale_factor,
+            origin,
+            size,
+            camera_nudge: Vec2::new(nudge.x, -nudge.y),
+        }
+    }
+
+    /// Logical window size that shows the screen at the smallest whole
+    /// scale no smaller than its unscaled size.
+    pub fn window_size(lanes: usize, scale_factor: f32) -> Vec2 {
+        Self::screen_size(lanes) * scale_factor.ceil() / scale_factor
+    }
+
+    /// Resize limits that keep the window big enough for the whole screen
+    /// at 1x, so resizing never crops it.
+    pub fn resize_constraints(lanes: usize, scale_factor: f32) -> WindowResizeConstraints {
+        let min = Self::screen_size(lanes) / scale_factor.min(1.0);
+
+        WindowResizeConstraints {
+            min_width: min.x,
+            min_height: min.y,
+            ..default()
+        }
+    }
+
+    /// Logical pixels per world unit and per UI pixel.
+    pub fn logical_scale(&self) -> f32 {
+        self.scale / self.scale_factor
+    }
+
+    /// World `x` of a logical window position, undoing the scale and the
+    /// camera offset `setup` applies for extra lanes.
+    pub fn window_to_world_x(&self, window_width: f32, lanes: usize, x: f32) -> f32 {
+        (x - window_width / 2.0) / self.logical_scale()
+            + TileScreen::camera_x(lanes)
+            + self.camera_nudge.x
+    }
+
+    /// The screen frame's style, in UI pixels like everything inside it.
+    pub fn frame_style(&self) -> Style {
+        Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                left: Val::Px(self.origin.x / self.scale),
+                top: Val::Px(self.origin.y / self.scale),
+                ..default()
+            },
+            size: Size::new(Val::Px(self.size.x), Val::Px(self.size.y)),
+            ..default()
+        }
+    }
+}
+
+impl Default for ScreenLayout {
+    fn default() -> Self {
+        Self::fit(Self::screen_size(LANES), 1.0, LANES)
+    }
+}
+
+/// The UI node covering the screen; side panel UI is placed inside it.
+#[derive(Component)]
+pub struct ScreenFrame;
+
+/// Side panel positions in unscaled pixels from the screen's top right
+/// corner, stacked from the size of what each entry holds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PanelLayout {
+    pub right: f32,
+    pub score: f32,
+    pub highscore: f32,
+    pub speed: f32,
+    pub next: f32,
+    /// Distance of the icons from the bottom.
+    pub icons: f32,
+    /// Distance of the pause text from the bottom.
+    pub pause: f32,
+}
+
+impl PanelLayout {
+    pub fn new() -> Self {
+        let entry = FONT_SIZE + DIGIT_HEIGHT as f32 * SEGMENT_TILE + PANEL_GAP;
+        let icon = ICON_SIZE as f32 * ICON_TILE;
+
+        Self {
+            right: WINDOW_PADDING,
+            score: PANEL_TOP,
+            highscore: PANEL_TOP + entry,
+            speed: PANEL_TOP + entry * 2.0,
+            next: PANEL_TOP + entry * 3.0,
+            icons: WINDOW_PADDING,
+            pause: WINDOW_PADDING + icon + ICON_GAP,
+        }
+    }
+
+    /// Right edge of the `index`th icon from the right.
+    pub fn icon_right(&self, index: usize) -> f32 {
+        self.right + index as f32 * (ICON_SIZE as f32 * ICON_TILE + ICON_GAP)
+    }
+
+    /// Top of the value under a label at `top`.
+    pub fn below_label(top: f32) -> f32 {
+        top + FONT_SIZE
+    }
+}
+
+impl Default for PanelLayout {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Spawns the screen frame for side panel UI to be placed in.
+pub fn spawn_screen_frame(commands: &mut Commands, layout: &ScreenLayout) -> Entity {
+    commands
+        .spawn((
+            NodeBundle {
+                style: layout.frame_style(),
+                background_color: Color::NONE.into(),
+                ..default()
+            },
+            ScreenFrame,
+        ))
+        .id()
+}
+
+/// Refits the screen to the window, scaling the camera and the UI together,
+/// and keeps the window from shrinking below the screen as the lane count
+/// or DPI changes. The first time, it also sizes a windowed window to a
+/// whole scale for the monitor's DPI.
+pub fn fit_screen(
+    mut windows: ResMut<Windows>,
+    race: Res<Race>,
+    mut layout: ResMut<ScreenLayout>,
+    mut ui_scale: ResMut<UiScale>,
+    mut camera_query: Query<(&mut Transform, &mut OrthographicProjection), With<Camera2d>>,
+    mut frame_query: Query<&mut Style, With<ScreenFrame>>,
+    mut sized: Local<bool>,
+) {
+    let Some(window) = windows.get_primary_mut() else {
+        return;
+    };
+    let lanes = race.config.lanes;
+    let scale_factor = window.scale_factor() as f32;
+
+    let constraints = ScreenLayout::resize_constraints(lanes, scale_factor);
+    if window.resize_constraints() != constraints {
+        window.set_resize_constraints(constraints);
+    }
+
+    if !std::mem::replace(&mut *sized, true) && window.mode() == WindowMode::Windowed {
+        let size = ScreenLayout::window_size(lanes, scale_factor);
+        window.set_resolution(size.x, size.y);
+        return;
+    }
+
+    let physical = Vec2::new(
+        window.physical_width() as f32,
+        window.physical_height() as f32,
+    );
+    let fitted = ScreenLayout::fit(physical, scale_factor, lanes);
+    if *layout == fitted {
+        return;
+    }
+
+    *layout = fitted;
+    ui_scale.scale = fitted.logical_scale() as f64;
+    for (mut transform, mut projection) in &mut camera_query {
+        projection.scale = 1.0 / fitted.logical_scale();
+        transform.translation.x = TileScreen::camera_x(lanes) + fitted.camera_nudge.x;
+        transform.translation.y = fitted.camera_nudge.y;
+    }
+    for mut style in &mut frame_query {
+        *style = fitted.frame_style();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn screen_scales_by_whole_factors_and_centres() {
+        let size = ScreenLayout::screen_size(LANES);
+
+        let exact = ScreenLayout::fit(size, 1.0, LANES);
+        assert_eq!((exact.scale, exact.origin), (1.0, Vec2::ZERO));
+
+        // Wide enough for 3x but only 2x tall: bars on the sides.
+        let layout = ScreenLayout::fit(Vec2::new(size.x * 3.0, size.y * 2.5), 1.0, LANES);
+        assert_eq!(layout.scale, 2.0);
+        assert_eq!(layout.origin, Vec2::new(size.x / 2.0, size.y / 4.0).round());
+
+        // Should a window still come out smaller, it crops around the centre
+        // rather than shrink.
+        let small = ScreenLayout::fit(size / 2.0, 1.0, LANES);
+        assert_eq!(small.scale, 1.0);
+        assert!(small.origin.x < 0.0);
+
+        let x = layout.window_to_world_x(size.x * 3.0, LANES, size.x * 1.5 + 40.0);
+        assert_eq!(
+            x,
+            20.0 + TileScreen::camera_x(LANES) + layout.camera_nudge.x
+        );
+    }
+
+    #[test]
+    fn high_dpi_scales_in_whole_physical_pixels() {
+        let size = ScreenLayout::screen_size(LANES);
+
+        // A 1.5x display gets a window sized for 2 physical pixels per unit.
+        let window = ScreenLayout::window_size(LANES, 1.5);
+        let layout = ScreenLayout::fit(window * 1.5, 1.5, LANES);
+        assert_eq!(layout.scale, 2.0);
+        assert_eq!(layout.origin, Vec2::ZERO);
+        assert!((layout.logical_scale() - 2.0 / 1.5).abs() < 1e-6);
+
+        // An odd bar is split on a whole pixel and the camera makes up the
+        // half.
+        let odd = ScreenLayout::fit(size * 2.0 + Vec2::new(3.0, 0.0), 1.0, LANES);
+        assert_eq!(odd.origin.x.fract(), 0.0);
+        assert_eq!(odd.camera_nudge.x.abs(), 0.25);
+        assert_eq!(odd.camera_nudge.y, 0.0);
+    }
+
+    #[test]
+    fn windows_cannot_shrink_below_the_screen() {
+        let size = ScreenLayout::screen_size(MAX_LANES);
+
+        let constraints = ScreenLayout::resize_constraints(MAX_LANES, 2.0);
+        assert_eq!(
+            (constraints.min_width, constraints.min_height),
+            (size.x, size.y)
+        );
+        assert_eq!(constraints.max_width, f32::INFINITY);
+
+        // Below one physical pixel per logical one, the minimum grows to
+        // keep a whole 1x screen.
+        let small_dpi = ScreenLayout::resize_constraints(MAX_LANES, 0.5);
+        assert_eq!(small_dpi.min_width * 0.5, size.x);
+        let fitted = ScreenLayout::fit(
+            Vec2::new(small_dpi.min_width, small_dpi.min_height) * 0.5,
+            0.5,
+            MAX_LANES,
+        );
+        assert_eq!((fitted.scale, fitted.origin), (1.0, Vec2::ZERO));
+    }
+
+    #[test]
+    fn panel_entries_stack_without_overlapping() {
+        let panel = PanelLayout::new();
+        let entry_height = FONT_SIZE + DIGIT_HEIGHT as f32 * SEGMENT_TILE;
+
+        for (top, below) in [
+            (panel.score, panel.highscore),
+            (panel.highscore, panel.speed),
+            (panel.speed, panel.next),
+        ] {
+            assert!(top + entry_height <= below);
+        }
+        let next_bottom = PanelLayout::below_label(panel.next) + PREVIEW_SIZE as f32 * PREVIEW_TILE;
+        assert!(next_bottom < WINDOW_HEIGHT - panel.pause - FONT_SIZE);
+        assert!(panel.icon_right(1) + ICON_SIZE as f32 * ICON_TILE <= UI_WIDTH);
+    }
+}
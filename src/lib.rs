@@ -0,0 +1,138 @@
+#![allow(clippy::too_many_arguments, clippy::type_complexity)]
+
+mod bot;
+mod cli;
+mod config;
+mod controls;
+mod demo;
+mod engine;
+mod entities;
+mod env;
+mod export;
+mod gamepad;
+mod ghosting;
+mod layout;
+mod locale;
+mod menu;
+mod mixer;
+mod music;
+mod pointer;
+mod race;
+mod replay;
+mod segment_display;
+mod settings;
+mod side_panel;
+mod spawner;
+mod steering;
+mod stereo;
+mod synth;
+mod systems;
+mod terminal;
+mod theme;
+mod tile_renderer;
+mod tile_screen;
+
+pub mod prelude {
+    pub use bevy::{audio::AudioSink, prelude::*, time::FixedTimestep};
+    pub use rand::prelude::*;
+
+    pub const FONT_SIZE: f32 = 32.0;
+
+    pub const PLAYER_Y: f32 = SCREEN_Y + (HALF_TILE * 4.0);
+
+    pub const UI_WIDTH: f32 = 120.0;
+    pub const WALL_SPACING: f32 = 5.0;
+    pub const CAR_SPACING: f32 = 9.0;
+    pub const TILE_SIZE: f32 = 20.0;
+    pub const HALF_TILE: f32 = TILE_SIZE / 2.0;
+    pub const COLUMN_SIZE: f32 = TILE_SIZE * 3.0;
+
+    pub const PADDING: usize = 2;
+
+    pub const WINDOW_PADDING: f32 = 20.0;
+    pub const WINDOW_HEIGHT: f32 = SCREEN_HEIGHT as f32 * TILE_SIZE + WINDOW_PADDING * 2.0;
+    pub const WINDOW_WIDTH: f32 = UI_WIDTH + SCREEN_WIDTH as f32 * TILE_SIZE + WINDOW_PADDING * 2.0;
+
+    pub const SCREEN_X: f32 = WINDOW_WIDTH / -2. + WINDOW_PADDING;
+    pub const SCREEN_Y: f32 = WINDOW_HEIGHT / -2. + WINDOW_PADDING;
+    pub const SCREEN_WIDTH: usize = 9 + PADDING * 2;
+    pub const SCREEN_HEIGHT: usize = 20;
+
+    #[derive(Default)]
+    pub struct ScoreEntities {
+        pub score: Option<Entity>,
+        pub highscore: Option<Entity>,
+        pub speed: Option<Entity>,
+    }
+
+    pub const LEADERBOARD_SIZE: usize = 5;
+    /// Places on the score readouts.
+    pub const SCORE_DIGITS: usize = 5;
+    pub const SPEED_DIGITS: usize = 2;
+
+    #[derive(Default, Resource)]
+    pub struct Scoreboard {
+        pub highscore: usize,
+        /// Best scores of this session, highest first.
+        pub leaderboard: Vec<usize>,
+        pub entities: ScoreEntities,
+    }
+
+    impl Scoreboard {
+        pub fn record(&mut self, score: usize) {
+            self.highscore = self.highscore.max(score);
+
+            let rank = self.leaderboard.partition_point(|&best| best >= score);
+            self.leaderboard.insert(rank, score);
+            self.leaderboard.truncate(LEADERBOARD_SIZE);
+        }
+    }
+
+    #[derive(Debug, Clone, Eq, PartialEq, Hash)]
+    pub enum GameState {
+        Menu,
+        Playing,
+        Demo,
+        Replay,
+        Paused,
+        Settings,
+        Controls,
+    }
+
+    #[derive(Resource)]
+    pub struct PauseData {
+        pub text_entity: Entity,
+    }
+
+    pub use crate::bot::*;
+    pub use crate::cli::*;
+    pub use crate::config::*;
+    pub use crate::controls::*;
+    pub use crate::demo::*;
+    pub use crate::engine::*;
+    pub use crate::entities::*;
+    pub use crate::env::*;
+    pub use crate::export::*;
+    pub use crate::gamepad::*;
+    pub use crate::ghosting::*;
+    pub use crate::layout::*;
+    pub use crate::locale::*;
+    pub use crate::menu::*;
+    pub use crate::mixer::*;
+    pub use crate::music::*;
+    pub use crate::pointer::*;
+    pub use crate::race::*;
+    pub use crate::replay::*;
+    pub use crate::segment_display::*;
+    pub use crate::settings::*;
+    pub use crate::side_panel::*;
+    pub use crate::spawner::*;
+    pub use crate::steering::*;
+    pub use crate::stereo::*;
+    pub use crate::synth::*;
+    pub use crate::systems::*;
+    pub use crate::terminal::*;
+    pub use crate::theme::*;
+    pub use crate::tile_renderer::*;
+    pub use crate::tile_screen::*;
+}
@@ -0,0 +1,179 @@
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+/// A language the UI ships strings for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    English,
+    Spanish,
+}
+
+impl Language {
+    pub const ALL: [Self; 2] = [Self::English, Self::Spanish];
+
+    /// The language's name in itself, so it can be found whatever the UI
+    /// is showing.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::English => "ENGLISH",
+            Self::Spanish => "ESPANOL",
+        }
+    }
+
+    /// The string table from `assets/locales`, built into the binary so the
+    /// headless tools and tests see the same text as the game.
+    pub fn source(&self) -> &'static str {
+        match self {
+            Self::English => include_str!("../assets/locales/en.ron"),
+            Self::Spanish => include_str!("../assets/locales/es.ron"),
+        }
+    }
+}
+
+/// Parses a string table, keyed like `menu.play`.
+pub fn parse_table(source: &str) -> Result<BTreeMap<String, String>, ron::error::SpannedError> {
+    ron::from_str(source)
+}
+
+/// The UI text in the chosen language. Keys the language leaves out are
+/// taken from English, and keys missing from English too show as
+/// themselves.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct Strings {
+    pub language: Language,
+    table: BTreeMap<String, String>,
+}
+
+impl Strings {
+    pub fn new(language: Language) -> Self {
+        Self::with_table(language, language.source())
+    }
+
+    /// Strings from `source` over the English ones. A malformed table is
+    /// all English, with a warning.
+    pub fn with_table(language: Language, source: &str) -> Self {
+        let mut table =
+            parse_table(Language::English.source()).expect("the English table is well formed");
+        match parse_table(source) {
+            Ok(translated) => table.extend(translated),
+            Err(err) => warn!("Ignoring the {:?} strings: {}", language, err),
+        }
+
+        Self { language, table }
+    }
+
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.table.get(key).map_or(key, String::as_str)
+    }
+
+    /// The text for `key` with each `{}` replaced by the next of `args`.
+    pub fn format(&self, key: &str, args: &[&dyn Display]) -> String {
+        let mut text = String::new();
+        for (index, piece) in self.get(key).split("{}").enumerate() {
+            if let Some(arg) = index.checked_sub(1).and_then(|index| args.get(index)) {
+                text.push_str(&arg.to_string());
+            }
+            text.push_str(piece);
+        }
+
+        text
+    }
+}
+
+impl Default for Strings {
+    fn default() -> Self {
+        Self::new(Language::default())
+    }
+}
+
+/// A UI text whose first section is the string for this key, rewritten
+/// when the language changes.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Localized(pub &'static str);
+
+/// Rewrites the window title and every `Localized` text in a new language.
+pub fn localize_texts(
+    strings: Res<Strings>,
+    mut windows: ResMut<Windows>,
+    mut texts: Query<(&Localized, &mut Text)>,
+) {
+    if !strings.is_changed() {
+        return;
+    }
+
+    if let Some(window) = windows.get_primary_mut() {
+        window.set_title(strings.get("window.title").to_string());
+    }
+    for (localized, mut text) in &mut texts {
+        text.sections[0].value = strings.get(localized.0).to_string();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_language_has_every_key() {
+        let english = parse_table(Language::English.source()).unwrap();
+
+        for language in Language::ALL {
+            let table = parse_table(language.source()).unwrap();
+            let missing: Vec<_> = english
+                .keys()
+                .filter(|key| !table.contains_key(*key))
+                .collect();
+            let unknown: Vec<_> = table
+                .keys()
+                .filter(|key| !english.contains_key(*key))
+                .collect();
+
+            assert!(missing.is_empty(), "{:?} lacks {:?}", language, missing);
+            assert!(
+                unknown.is_empty(),
+                "{:?} has unused {:?}",
+                language,
+                unknown
+            );
+            for (key, text) in &table {
+                let holes = |text: &str| text.matches("{}").count();
+                assert_eq!(holes(text), holes(&english[key]), "{:?} {}", language, key);
+            }
+        }
+    }
+
+    #[test]
+    fn every_label_the_game_asks_for_is_shipped() {
+        let english = parse_table(Language::English.source()).unwrap();
+        let keys = SettingsItem::ALL
+            .iter()
+            .map(SettingsItem::key)
+            .chain(InputAction::ALL.iter().map(InputAction::key))
+            .chain(ThemePreset::ALL.iter().map(ThemePreset::key));
+
+        for key in keys {
+            assert!(english.contains_key(key), "{}", key);
+        }
+    }
+
+    #[test]
+    fn missing_keys_fall_back_to_english() {
+        let strings = Strings::with_table(Language::Spanish, r#"{ "menu.play": "JUGAR" }"#);
+        assert_eq!(strings.get("menu.play"), "JUGAR");
+        assert_eq!(strings.get("menu.quit"), "QUIT");
+        assert_eq!(strings.get("no.such.key"), "no.such.key");
+
+        let broken = Strings::with_table(Language::Spanish, "{ oops");
+        assert_eq!(broken.get("menu.play"), "PLAY");
+
+        let spanish = Strings::new(Language::Spanish);
+        assert_eq!(spanish.format("menu.lanes", &[&3]), "3 CARRILES");
+        assert_eq!(
+            spanish.format("controls.taken", &[&"Space", &"TURBO"]),
+            "Space ES TURBO"
+        );
+    }
+}
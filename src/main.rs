@@ -1,145 +1,202 @@
-mod entities;
-mod spawner;
-mod systems;
-mod tile_screen;
+use bevy::diagnostic::FrameTimeDiagnosticsPlugin;
+use bevy_retro_racing::prelude::*;
+use clap::Parser;
 
-mod prelude {
-    pub use bevy::{audio::AudioSink, prelude::*, time::FixedTimestep};
-    pub use rand::prelude::*;
-
-    pub const BG_COLOR: &str = "8d9e7b";
-    pub const FONT_SIZE: f32 = 32.0;
-
-    pub const PLAYER_Y: f32 = SCREEN_Y + (HALF_TILE * 4.0);
-    pub const TILE_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.98);
-
-    pub const UI_WIDTH: f32 = 120.0;
-    pub const WALL_SPACING: f32 = 5.0;
-    pub const CAR_SPACING: f32 = 9.0;
-    pub const TILE_SIZE: f32 = 20.0;
-    pub const HALF_TILE: f32 = TILE_SIZE / 2.0;
-    pub const COLUMN_SIZE: f32 = TILE_SIZE * 3.0;
-
-    pub const PADDING: usize = 2;
-
-    pub const WINDOW_PADDING: f32 = 20.0;
-    pub const WINDOW_HEIGHT: f32 = SCREEN_HEIGHT as f32 * TILE_SIZE + WINDOW_PADDING * 2.0;
-    pub const WINDOW_WIDTH: f32 = UI_WIDTH + SCREEN_WIDTH as f32 * TILE_SIZE + WINDOW_PADDING * 2.0;
-
-    pub const SCREEN_X: f32 = WINDOW_WIDTH as f32 / -2. + WINDOW_PADDING;
-    pub const SCREEN_Y: f32 = WINDOW_HEIGHT as f32 / -2. + WINDOW_PADDING;
-    pub const SCREEN_WIDTH: usize = 9 + PADDING * 2;
-    pub const SCREEN_HEIGHT: usize = 20;
-
-    pub const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
-    pub const HOVERED_BUTTON: Color = Color::rgb(0.25, 0.25, 0.25);
-    pub const PRESSED_BUTTON: Color = Color::rgb(0.35, 0.75, 0.35);
-
-    #[derive(Default)]
-    pub struct ScoreEntities {
-        pub score: Option<Entity>,
-        pub highscore: Option<Entity>,
-    }
-
-    #[derive(Default, Resource)]
-    pub struct Scoreboard {
-        pub score: usize,
-        pub highscore: usize,
-        pub entities: ScoreEntities,
-    }
+fn main() {
+    let options = LaunchOptions::parse();
 
-    #[derive(Debug, Clone, Eq, PartialEq, Hash)]
-    pub enum GameState {
-        Menu,
-        Playing,
+    if options.headless {
+        if let Err(err) = options.run_headless() {
+            eprintln!("error: {}", err);
+            std::process::exit(1);
+        }
+        return;
     }
 
-    #[derive(Resource)]
-    pub struct MenuData {
-        pub button_entity: Entity,
-    }
+    let mut app = App::new();
 
-    #[derive(Resource)]
-    pub struct GameData {
-        pub move_timer: Timer,
-        pub is_boosting: bool,
-        pub boost_factor: f32,
-        pub speed_factor: f32,
-    }
+    let initial_state = match &options.replay {
+        Some(path) => {
+            let replay = Replay::load(path).unwrap_or_else(|err| {
+                eprintln!("error: could not load {}: {}", path.display(), err);
+                std::process::exit(1);
+            });
 
-    impl GameData {
-        pub fn new() -> Self {
-            Self {
-                move_timer: Timer::from_seconds(0.08, TimerMode::Repeating),
-                speed_factor: 1.0,
-                boost_factor: 2.0,
-                is_boosting: false,
-            }
+            app.insert_resource(Race::new(replay.config))
+                .insert_resource(Playback { replay, frame: 0 });
+            GameState::Replay
         }
-    }
+        None => {
+            let mut race = Race::new(options.race_config());
+            let path = options
+                .record
+                .clone()
+                .or_else(|| config_path(LAST_RUN_FILE));
+            if let Some(path) = path {
+                race.record();
+                app.insert_resource(ReplayRecorder { path });
+            }
 
-    impl Default for GameData {
-        fn default() -> Self {
-            Self::new()
+            app.insert_resource(race);
+            GameState::Menu
         }
-    }
+    };
+    let lanes = app.world.resource::<Race>().config.lanes;
 
-    pub use crate::entities::*;
-    pub use crate::spawner::*;
-    pub use crate::systems::*;
-    pub use crate::tile_screen::*;
-}
+    let settings = Settings::load_user();
+    let scale = ScaleOverride(options.scale);
 
-use prelude::*;
+    let theme = Theme::preset(settings.theme);
+    let strings = Strings::new(settings.language);
+    let steering = SteerQueue::new(settings.repeat);
 
-fn main() {
-    println!("{}, {}", WINDOW_WIDTH, WINDOW_HEIGHT);
-    App::new()
-        .init_resource::<Scoreboard>()
-        .init_resource::<GameData>()
-        .insert_resource(ClearColor(Color::hex(BG_COLOR).unwrap()))
+    app.init_resource::<Scoreboard>()
+        .insert_resource(KeyBindings::load_user())
+        .insert_resource(ClearColor(theme.background))
+        .insert_resource(theme)
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             window: WindowDescriptor {
-                title: "Carritos".to_string(),
-                resizable: false,
-                width: WINDOW_WIDTH,
+                title: strings.get("window.title").to_string(),
+                resizable: true,
+                resize_constraints: ScreenLayout::resize_constraints(lanes, 1.0),
+                width: TileScreen::window_width(lanes),
                 height: WINDOW_HEIGHT,
+                scale_factor_override: settings.window_scale_with(scale),
+                mode: settings.window_mode(),
                 ..default()
             },
             ..default()
         }))
-        .add_state(GameState::Menu)
+        .add_plugin(FrameTimeDiagnosticsPlugin)
+        .add_plugin(StereoPlugin)
+        .add_plugin(SynthPlugin)
+        .add_plugin(MusicPlugin)
+        .insert_resource(settings)
+        .insert_resource(MuteOverride(options.mute))
+        .insert_resource(scale)
+        .insert_resource(strings)
+        .add_state(initial_state)
+        .init_resource::<ScreenLayout>()
         .add_startup_system(setup)
+        .add_startup_system(setup_fps)
+        .add_system(apply_settings)
+        .add_system_to_stage(CoreStage::PostUpdate, fit_screen)
+        .add_system_to_stage(CoreStage::PostUpdate, apply_theme)
+        .add_system_to_stage(CoreStage::PostUpdate, render_tiles)
+        .add_system_to_stage(CoreStage::PostUpdate, update_scoreboard)
+        .add_system_to_stage(
+            CoreStage::PostUpdate,
+            draw_segment_readouts.after(update_scoreboard),
+        )
+        .add_system_to_stage(CoreStage::PostUpdate, update_side_panel)
+        .add_system_to_stage(CoreStage::PostUpdate, localize_texts)
+        .add_system(update_fps)
+        .add_system(toggle_mute)
+        .add_startup_system(setup_touch_controls)
+        .add_system(show_touch_controls)
         .add_event::<CollisionEvent>()
+        .init_resource::<AttractMode>()
+        .insert_resource(steering)
+        .init_resource::<Gestures>()
+        .init_resource::<LcdPersistence>()
         .add_system_set(
             SystemSet::on_enter(GameState::Menu)
                 .with_system(setup_menu)
-                .with_system(spawn_walls),
+                .with_system(spawn_walls)
+                .with_system(reset_idle),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::Menu)
+                .with_system(menu)
+                .with_system(update_menu.after(menu))
+                .with_system(resize_playfield.after(menu))
+                .with_system(start_demo.before(menu)),
         )
-        .add_system_set(SystemSet::on_update(GameState::Menu).with_system(menu))
+        .add_system_set(SystemSet::on_resume(GameState::Menu).with_system(reset_idle))
         .add_system_set(SystemSet::on_exit(GameState::Menu).with_system(cleanup_menu))
         .add_system_set(
             SystemSet::on_enter(GameState::Playing)
-                .with_system(play_motor_sound)
+                .with_system(reset_steering)
+                .with_system(start_countdown)
+                .with_system(reset_gestures)
                 .with_system(spawn_player)
                 .with_system(spawn_walls)
                 .with_system(spawn_enemies),
         )
         .add_system_set(
-            SystemSet::on_update(GameState::Playing)
-                .with_system(accelerate.before(check_collisions))
-                .with_system(move_player.before(check_collisions))
+            race_systems(GameState::Playing)
+                .with_system(move_player.label(RaceLabel::Input))
+                .with_system(boost_player.label(RaceLabel::Input))
+                .with_system(gamepad_player.label(RaceLabel::Input))
+                .with_system(pointer_player.label(RaceLabel::Input))
+                .with_system(touch_buttons.label(RaceLabel::Input))
+                .with_system(
+                    steer_player
+                        .label(RaceLabel::Input)
+                        .after(move_player)
+                        .after(gamepad_player)
+                        .after(pointer_player)
+                        .after(touch_buttons),
+                )
+                .with_system(pause_game.after(check_collisions))
+                .with_system(count_down.before(RaceLabel::Rules))
+                .with_system(accelerate.label(RaceLabel::Rules).after(RaceLabel::Input))
+                .with_system(level_up_jingle.after(RaceLabel::Rules))
+                .with_system(pass_by.after(RaceLabel::Rules))
+                .with_system(play_motor_sound.before(rev_engine))
+                .with_system(save_replay.after(RaceLabel::Rules).before(check_collisions))
+                .with_system(check_collisions.after(RaceLabel::Rules))
                 .with_system(play_explosion_sound.after(check_collisions))
-                .with_system(boost_player)
-                .with_system(wall_respawn)
-                .with_system(enemy_respawn)
-                .with_system(check_collisions)
-                .with_system(update_scoreboard),
+                .with_system(rev_engine.after(RaceLabel::Rules).before(check_collisions)),
+        )
+        .add_system_set(SystemSet::on_enter(GameState::Settings).with_system(setup_settings))
+        .add_system_set(
+            SystemSet::on_update(GameState::Settings)
+                .with_system(settings_menu)
+                .with_system(update_settings.after(settings_menu)),
+        )
+        .add_system_set(SystemSet::on_exit(GameState::Settings).with_system(cleanup_settings))
+        .add_system_set(SystemSet::on_enter(GameState::Controls).with_system(setup_controls))
+        .add_system_set(
+            SystemSet::on_update(GameState::Controls)
+                .with_system(controls)
+                .with_system(update_controls.after(controls)),
+        )
+        .add_system_set(SystemSet::on_exit(GameState::Controls).with_system(cleanup_controls))
+        .add_system_set(SystemSet::on_pause(GameState::Playing).with_system(pause_motor_sound))
+        .add_system_set(SystemSet::on_resume(GameState::Playing).with_system(resume_motor_sound))
+        .add_system_set(SystemSet::on_enter(GameState::Paused).with_system(setup_pause))
+        .add_system_set(SystemSet::on_update(GameState::Paused).with_system(resume_game))
+        .add_system_set(SystemSet::on_exit(GameState::Paused).with_system(cleanup_pause))
+        .add_system_set(
+            SystemSet::on_enter(GameState::Demo)
+                .with_system(spawn_player)
+                .with_system(spawn_enemies),
+        )
+        .add_system_set(
+            race_systems(GameState::Demo)
+                .with_system(autopilot.label(RaceLabel::Input))
+                .with_system(accelerate.label(RaceLabel::Rules).after(RaceLabel::Input))
+                .with_system(stop_demo.after(RaceLabel::Rules)),
+        )
+        .add_system_set(
+            SystemSet::on_exit(GameState::Demo)
+                .with_system(cleanup_demo)
+                .with_system(position_walls.after(cleanup_demo)),
+        )
+        .add_system_set(
+            SystemSet::on_enter(GameState::Replay)
+                .with_system(spawn_player)
+                .with_system(spawn_walls)
+                .with_system(spawn_enemies),
         )
         .add_system_set(
-            SystemSet::new()
-                .with_run_criteria(FixedTimestep::step(1.0))
-                .with_system(increment_scoreboard),
+            race_systems(GameState::Replay)
+                .with_system(play_replay.label(RaceLabel::Rules))
+                .with_system(check_collisions.after(RaceLabel::Rules))
+                .with_system(end_replay.after(RaceLabel::Rules).before(check_collisions))
+                .with_system(play_explosion_sound.after(check_collisions)),
         )
+        .add_system_set(SystemSet::on_exit(GameState::Replay).with_system(cleanup_replay))
         .run();
 }
@@ -0,0 +1,438 @@
+use crate::prelude::*;
+use bevy::app::AppExit;
+use std::collections::HashMap;
+
+pub const LEVEL_STEP: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuPage {
+    Main,
+    Mode,
+    Speed,
+    Leaderboard,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuAction {
+    Play,
+    Open(MenuPage),
+    Lanes(usize),
+    Level(usize),
+    Settings,
+    Quit,
+    Back,
+    Nothing,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MenuItem {
+    pub label: String,
+    pub action: MenuAction,
+}
+
+impl MenuItem {
+    fn new(label: impl Into<String>, action: MenuAction) -> Self {
+        Self {
+            label: label.into(),
+            action,
+        }
+    }
+}
+
+impl MenuPage {
+    /// The menu tree, entries top-down. Every submenu ends with BACK.
+    pub fn items(self, scoreboard: &Scoreboard, strings: &Strings) -> Vec<MenuItem> {
+        let item = |key, action| MenuItem::new(strings.get(key), action);
+        let back = item("menu.back", MenuAction::Back);
+
+        match self {
+            Self::Main => vec![
+                item("menu.play", MenuAction::Play),
+                item("menu.mode", MenuAction::Open(Self::Mode)),
+                item("menu.speed", MenuAction::Open(Self::Speed)),
+                item("menu.leaderboard", MenuAction::Open(Self::Leaderboard)),
+                item("menu.settings", MenuAction::Settings),
+                item("menu.quit", MenuAction::Quit),
+            ],
+            Self::Mode => LANE_RANGE
+                .map(|lanes| {
+                    MenuItem::new(
+                        strings.format("menu.lanes", &[&lanes]),
+                        MenuAction::Lanes(lanes),
+                    )
+                })
+                .chain([back])
+                .collect(),
+            Self::Speed => (0..=MAX_LEVEL)
+                .step_by(LEVEL_STEP)
+                .map(|level| {
+                    MenuItem::new(
+                        strings.format("menu.level", &[&level]),
+                        MenuAction::Level(level),
+                    )
+                })
+                .chain([back])
+                .collect(),
+            Self::Leaderboard => {
+                let mut items: Vec<_> = scoreboard
+                    .leaderboard
+                    .iter()
+                    .enumerate()
+                    .map(|(rank, score)| {
+                        MenuItem::new(format!("{}. {}", rank + 1, score), MenuAction::Nothing)
+                    })
+                    .collect();
+                if items.is_empty() {
+                    items.push(item("menu.no_runs", MenuAction::Nothing));
+                }
+                items.push(back);
+                items
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuInput {
+    Up,
+    Down,
+    Select,
+    Back,
+}
+
+#[derive(Component)]
+pub struct MenuRow(pub usize);
+
+#[derive(Resource)]
+pub struct MenuData {
+    pub root: Entity,
+    pub page: MenuPage,
+    pub items: Vec<MenuItem>,
+    pub selected: usize,
+    /// The pages above this one and what was selected on them.
+    pub parents: Vec<(MenuPage, usize)>,
+    /// The rows need respawning for a new page.
+    pub dirty: bool,
+    font: Handle<Font>,
+}
+
+impl MenuData {
+    /// Moves the selection, or returns the action of the entry picked.
+    pub fn navigate(&mut self, input: MenuInput) -> Option<MenuAction> {
+        let len = self.items.len();
+
+        match input {
+            MenuInput::Up => self.selected = (self.selected + len - 1) % len,
+            MenuInput::Down => self.selected = (self.selected + 1) % len,
+            MenuInput::Select => return Some(self.items[self.selected].action),
+            MenuInput::Back if !self.parents.is_empty() => return Some(MenuAction::Back),
+            MenuInput::Back => {}
+        }
+
+        None
+    }
+
+    pub fn open(&mut self, page: MenuPage, scoreboard: &Scoreboard, strings: &Strings) {
+        self.parents.push((self.page, self.selected));
+        self.show(page, 0, scoreboard, strings);
+    }
+
+    pub fn back(&mut self, scoreboard: &Scoreboard, strings: &Strings) {
+        if let Some((page, selected)) = self.parents.pop() {
+            self.show(page, selected, scoreboard, strings);
+        }
+    }
+
+    fn show(
+        &mut self,
+        page: MenuPage,
+        selected: usize,
+        scoreboard: &Scoreboard,
+        strings: &Strings,
+    ) {
+        self.page = page;
+        self.items = page.items(scoreboard, strings);
+        self.selected = selected.min(self.items.len() - 1);
+        self.dirty = true;
+    }
+}
+
+/// Whether the entry is the option currently in use.
+fn is_current(action: MenuAction, race: &Race) -> bool {
+    match action {
+        MenuAction::Lanes(lanes) => race.config.lanes == lanes,
+        MenuAction::Level(level) => race.config.level == level,
+        _ => false,
+    }
+}
+
+pub fn setup_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    scoreboard: Res<Scoreboard>,
+    strings: Res<Strings>,
+) {
+    let root = commands
+        .spawn(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                position: UiRect {
+                    right: Val::Px((UI_WIDTH - WINDOW_PADDING) / 2.0),
+                    ..default()
+                },
+                ..default()
+            },
+            background_color: Color::NONE.into(),
+            ..default()
+        })
+        .id();
+
+    commands.insert_resource(MenuData {
+        root,
+        page: MenuPage::Main,
+        items: MenuPage::Main.items(&scoreboard, &strings),
+        selected: 0,
+        parents: Vec::new(),
+        dirty: true,
+        font: asset_server.load("fonts/Calculator.ttf"),
+    });
+}
+
+pub fn menu(
+    mut state: ResMut<State<GameState>>,
+    mut menu: ResMut<MenuData>,
+    mut race: ResMut<Race>,
+    scoreboard: Res<Scoreboard>,
+    strings: Res<Strings>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    gamepads: Res<Gamepads>,
+    mut gamepad_input: ResMut<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    mut sticks: Local<HashMap<Gamepad, Steer>>,
+    interaction_query: Query<(&Interaction, &MenuRow), Changed<Interaction>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let mut inputs = Vec::new();
+
+    for (interaction, row) in &interaction_query {
+        match interaction {
+            Interaction::Clicked => {
+                menu.selected = row.0;
+                inputs.push(MenuInput::Select);
+            }
+            Interaction::Hovered => menu.selected = row.0,
+            Interaction::None => {}
+        }
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Up) {
+        inputs.push(MenuInput::Up);
+    }
+    if keyboard_input.just_pressed(KeyCode::Down) {
+        inputs.push(MenuInput::Down);
+    }
+    // Consumed so the screen we switch to does not act on them too.
+    if bindings.clear_just_pressed(&mut keyboard_input, InputAction::Confirm) {
+        inputs.push(MenuInput::Select);
+    }
+    if keyboard_input.clear_just_pressed(KeyCode::Escape)
+        || keyboard_input.clear_just_pressed(KeyCode::Back)
+    {
+        inputs.push(MenuInput::Back);
+    }
+
+    if gamepad_just_pressed(&gamepads, &gamepad_input, GamepadButtonType::DPadUp) {
+        inputs.push(MenuInput::Up);
+    }
+    if gamepad_just_pressed(&gamepads, &gamepad_input, GamepadButtonType::DPadDown) {
+        inputs.push(MenuInput::Down);
+    }
+    for gamepad in gamepads.iter() {
+        let y = axes
+            .get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickY))
+            .unwrap_or_default();
+        // The stick latch works the same vertically: "left" is down.
+        match stick_steer(sticks.entry(gamepad).or_default(), y) {
+            Steer::Left => inputs.push(MenuInput::Down),
+            Steer::Right => inputs.push(MenuInput::Up),
+            Steer::Straight => {}
+        }
+    }
+    if gamepad_clear_just_pressed(&gamepads, &mut gamepad_input, GamepadButtonType::South)
+        || gamepad_clear_just_pressed(&gamepads, &mut gamepad_input, GamepadButtonType::Start)
+    {
+        inputs.push(MenuInput::Select);
+    }
+    if gamepad_clear_just_pressed(&gamepads, &mut gamepad_input, GamepadButtonType::East) {
+        inputs.push(MenuInput::Back);
+    }
+
+    for input in inputs {
+        let Some(action) = menu.navigate(input) else {
+            continue;
+        };
+
+        match action {
+            MenuAction::Play => queue_transition(state.set(GameState::Playing)),
+            MenuAction::Open(page) => menu.open(page, &scoreboard, &strings),
+            MenuAction::Back => menu.back(&scoreboard, &strings),
+            MenuAction::Lanes(lanes) => {
+                let level = race.config.level;
+                race.configure(lanes, level);
+                menu.back(&scoreboard, &strings);
+            }
+            MenuAction::Level(level) => {
+                let lanes = race.config.lanes;
+                race.configure(lanes, level);
+                menu.back(&scoreboard, &strings);
+            }
+            MenuAction::Settings => queue_transition(state.set(GameState::Settings)),
+            MenuAction::Quit => exit.send(AppExit),
+            MenuAction::Nothing => {}
+        }
+        // Anything after a pick was meant for the old page.
+        break;
+    }
+}
+
+pub fn update_menu(
+    mut commands: Commands,
+    mut menu: ResMut<MenuData>,
+    race: Res<Race>,
+    theme: Res<Theme>,
+    mut rows_query: Query<(&MenuRow, &Interaction, &Children, &mut BackgroundColor)>,
+    mut text_query: Query<&mut Text>,
+) {
+    if menu.dirty {
+        menu.dirty = false;
+
+        let text_style = TextStyle {
+            font: menu.font.clone(),
+            font_size: 20.0,
+            ..default()
+        };
+
+        commands.entity(menu.root).despawn_descendants();
+        commands.entity(menu.root).with_children(|parent| {
+            for index in 0..menu.items.len() {
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                size: Size::new(Val::Px(160.0), Val::Px(36.0)),
+                                margin: UiRect::all(Val::Px(4.0)),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            background_color: theme.button.into(),
+                            ..default()
+                        },
+                        MenuRow(index),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            TextBundle::from_section("", text_style.clone()),
+                            Themed::ButtonText,
+                        ));
+                    });
+            }
+        });
+        return;
+    }
+
+    for (row, interaction, children, mut color) in &mut rows_query {
+        let Some(item) = menu.items.get(row.0) else {
+            continue;
+        };
+
+        *color = if *interaction == Interaction::Clicked {
+            theme.button_pressed.into()
+        } else if row.0 == menu.selected {
+            theme.button_hovered.into()
+        } else {
+            theme.button.into()
+        };
+
+        if let Ok(mut text) = text_query.get_mut(children[0]) {
+            text.sections[0].value = if is_current(item.action, &race) {
+                format!("* {}", item.label)
+            } else {
+                item.label.clone()
+            };
+        }
+    }
+}
+
+pub fn cleanup_menu(mut commands: Commands, menu: Res<MenuData>) {
+    commands.entity(menu.root).despawn_recursive();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn menu() -> MenuData {
+        let scoreboard = Scoreboard::default();
+
+        MenuData {
+            root: Entity::from_raw(0),
+            page: MenuPage::Main,
+            items: MenuPage::Main.items(&scoreboard, &Strings::default()),
+            selected: 0,
+            parents: Vec::new(),
+            dirty: false,
+            font: Handle::default(),
+        }
+    }
+
+    #[test]
+    fn selection_wraps_around() {
+        let mut menu = menu();
+
+        assert_eq!(menu.navigate(MenuInput::Up), None);
+        assert_eq!(menu.items[menu.selected].action, MenuAction::Quit);
+        assert_eq!(menu.navigate(MenuInput::Down), None);
+        assert_eq!(menu.navigate(MenuInput::Select), Some(MenuAction::Play));
+    }
+
+    #[test]
+    fn submenus_return_to_their_entry() {
+        let scoreboard = Scoreboard::default();
+        let strings = Strings::default();
+        let mut menu = menu();
+
+        menu.navigate(MenuInput::Down);
+        menu.navigate(MenuInput::Down);
+        let Some(MenuAction::Open(page)) = menu.navigate(MenuInput::Select) else {
+            panic!("SPEED opens a submenu");
+        };
+        menu.open(page, &scoreboard, &strings);
+        assert_eq!(menu.page, MenuPage::Speed);
+        assert_eq!(menu.navigate(MenuInput::Down), None);
+        assert_eq!(
+            menu.navigate(MenuInput::Select),
+            Some(MenuAction::Level(LEVEL_STEP))
+        );
+
+        assert_eq!(menu.navigate(MenuInput::Back), Some(MenuAction::Back));
+        menu.back(&scoreboard, &strings);
+        assert_eq!((menu.page, menu.selected), (MenuPage::Main, 2));
+        assert_eq!(menu.navigate(MenuInput::Back), None);
+    }
+
+    #[test]
+    fn every_submenu_can_go_back() {
+        let scoreboard = Scoreboard::default();
+        let strings = Strings::default();
+
+        for page in [MenuPage::Mode, MenuPage::Speed, MenuPage::Leaderboard] {
+            let items = page.items(&scoreboard, &strings);
+            assert_eq!(items.last().unwrap().action, MenuAction::Back, "{:?}", page);
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use crate::prelude::*;
+use bevy::asset::Asset;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Bus {
+    Master,
+    Engine,
+    Effects,
+    Music,
+}
+
+/// Mutes every bus for this session only, as `--mute` asks. Kept apart
+/// from `Settings` so it is never saved.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MuteOverride(pub bool);
+
+/// Per-bus volumes, each from 0 to 1. Every other bus also goes through
+/// the master bus.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Mixer {
+    pub master: f32,
+    pub engine: f32,
+    pub effects: f32,
+    pub music: f32,
+    pub muted: bool,
+}
+
+impl Default for Mixer {
+    fn default() -> Self {
+        Self {
+            master: 1.0,
+            engine: 1.0,
+            effects: 1.0,
+            music: 0.6,
+            muted: false,
+        }
+    }
+}
+
+impl Mixer {
+    pub fn volume(&self, bus: Bus) -> f32 {
+        match bus {
+            Bus::Master => self.master,
+            Bus::Engine => self.engine,
+            Bus::Effects => self.effects,
+            Bus::Music => self.music,
+        }
+    }
+
+    pub fn volume_mut(&mut self, bus: Bus) -> &mut f32 {
+        match bus {
+            Bus::Master => &mut self.master,
+            Bus::Engine => &mut self.engine,
+            Bus::Effects => &mut self.effects,
+            Bus::Music => &mut self.music,
+        }
+    }
+
+    /// Whether sound is off, from the settings or for the session.
+    pub fn is_muted(&self, session: MuteOverride) -> bool {
+        self.muted || session.0
+    }
+
+    /// What a sound on `bus` is multiplied by.
+    pub fn gain(&self, bus: Bus, session: MuteOverride) -> f32 {
+        if self.is_muted(session) {
+            return 0.0;
+        }
+
+        match bus {
+            Bus::Master => self.master,
+            _ => self.master * self.volume(bus),
+        }
+    }
+
+    /// Plays a one-shot sound on `bus`; nothing at all while muted.
+    pub fn play<Source: Asset + Decodable>(
+        &self,
+        audio: &Audio<Source>,
+        bus: Bus,
+        sound: Handle<Source>,
+        session: MuteOverride,
+    ) {
+        if self.is_muted(session) {
+            return;
+        }
+
+        audio.play_with_settings(
+            sound,
+            PlaybackSettings::ONCE.with_volume(self.gain(bus, session)),
+        );
+    }
+}
+
+pub fn toggle_mute(
+    state: Res<State<GameState>>,
+    keyboard_input: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut settings: ResMut<Settings>,
+    mut session: ResMut<MuteOverride>,
+) {
+    // Keys pressed on the controls screen are being bound, not used.
+    if *state.current() == GameState::Controls {
+        return;
+    }
+
+    if bindings.just_pressed(&keyboard_input, InputAction::Mute) {
+        // Unmuting a `--mute` session lifts the override and leaves the
+        // saved setting alone.
+        if session.0 {
+            session.0 = false;
+        } else {
+            settings.mixer.muted = !settings.mixer.muted;
+            settings.save_user();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buses_go_through_master_and_mute() {
+        let mut mixer = Mixer {
+            master: 0.5,
+            engine: 0.8,
+            ..default()
+        };
+
+        let unmuted = MuteOverride(false);
+        assert_eq!(mixer.gain(Bus::Master, unmuted), 0.5);
+        assert_eq!(mixer.gain(Bus::Engine, unmuted), 0.4);
+        assert_eq!(mixer.gain(Bus::Effects, unmuted), 0.5);
+
+        // The session override mutes without touching the saved flag.
+        for bus in [Bus::Master, Bus::Engine, Bus::Effects, Bus::Music] {
+            assert_eq!(mixer.gain(bus, MuteOverride(true)), 0.0);
+        }
+        assert!(!mixer.muted);
+
+        mixer.muted = true;
+        for bus in [Bus::Master, Bus::Engine, Bus::Effects, Bus::Music] {
+            assert_eq!(mixer.gain(bus, unmuted), 0.0);
+        }
+    }
+}
@@ -0,0 +1,294 @@
+use crate::prelude::*;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// How long one track takes to fade into the next.
+pub const CROSSFADE: Duration = Duration::from_secs(1);
+/// Music level while ducked under the explosion.
+pub const DUCK_LEVEL: f32 = 0.25;
+/// How long the music stays ducked before coming back.
+pub const DUCK_HOLD: Duration = Duration::from_millis(900);
+pub const DUCK_RECOVERY: Duration = Duration::from_millis(700);
+
+// Square-wave pitches, in Hz.
+const C4: f32 = 261.63;
+const D4: f32 = 293.66;
+const E4: f32 = 329.63;
+const F4: f32 = 349.23;
+const G4: f32 = 392.0;
+const A4: f32 = 440.0;
+const C5: f32 = 523.25;
+const E3: f32 = 164.81;
+const G3: f32 = 196.0;
+const A3: f32 = 220.0;
+const B3: f32 = 246.94;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Track {
+    Menu,
+    Race,
+    GameOver,
+}
+
+impl Track {
+    pub const ALL: [Self; 3] = [Self::Menu, Self::Race, Self::GameOver];
+
+    /// What plays in `state`; the menu after a crash is the game over screen.
+    pub fn for_state(state: &GameState, game_over: bool) -> Self {
+        match state {
+            GameState::Playing | GameState::Paused | GameState::Replay => Self::Race,
+            GameState::Demo => Self::Menu,
+            GameState::Menu | GameState::Settings | GameState::Controls if game_over => {
+                Self::GameOver
+            }
+            GameState::Menu | GameState::Settings | GameState::Controls => Self::Menu,
+        }
+    }
+
+    /// One loop of the track.
+    pub fn notes(&self) -> Vec<Note> {
+        let tune = |pitches: &[f32], millis| {
+            pitches
+                .iter()
+                .map(|&pitch| Note::square(pitch, millis))
+                .collect()
+        };
+
+        match self {
+            Self::Menu => tune(
+                &[
+                    C4, E4, G4, E4, A3, C4, E4, C4, F4, A4, C5, A4, G3, B3, D4, B3,
+                ],
+                180,
+            ),
+            Self::Race => tune(
+                &[
+                    E3, E3, B3, E3, E3, A3, E3, G3, E3, E3, B3, E3, D4, B3, A3, G3,
+                ],
+                110,
+            ),
+            Self::GameOver => {
+                let mut notes: Vec<Note> = tune(&[G4, F4, E4, D4, C4], 260);
+                notes.push(Note::rest(1200));
+                notes
+            }
+        }
+    }
+}
+
+/// Fade levels of the tracks that are playing, and the ducking on top of
+/// them. Kept apart from the audio sinks so the mixing is testable.
+#[derive(Resource, Debug)]
+pub struct MusicMix {
+    track: Option<Track>,
+    fades: Vec<(Track, f32)>,
+    duck: f32,
+    duck_hold: Duration,
+}
+
+impl Default for MusicMix {
+    fn default() -> Self {
+        Self {
+            track: None,
+            fades: Vec::new(),
+            duck: 1.0,
+            duck_hold: Duration::ZERO,
+        }
+    }
+}
+
+impl MusicMix {
+    /// Fades `track` in and whatever else was playing out.
+    pub fn play(&mut self, track: Track) {
+        if self.track == Some(track) {
+            return;
+        }
+
+        self.track = Some(track);
+        if !self.fades.iter().any(|&(fading, _)| fading == track) {
+            self.fades.push((track, 0.0));
+        }
+    }
+
+    /// Drops the music under a loud effect for a moment.
+    pub fn duck(&mut self) {
+        self.duck = DUCK_LEVEL;
+        self.duck_hold = DUCK_HOLD;
+    }
+
+    pub fn update(&mut self, delta: Duration) {
+        let step = delta.as_secs_f32() / CROSSFADE.as_secs_f32();
+        for (track, fade) in self.fades.iter_mut() {
+            if Some(*track) == self.track {
+                *fade = (*fade + step).min(1.0);
+            } else {
+                *fade = (*fade - step).max(0.0);
+            }
+        }
+
+        let current = self.track;
+        self.fades
+            .retain(|&(track, fade)| fade > 0.0 || Some(track) == current);
+
+        if self.duck_hold > delta {
+            self.duck_hold -= delta;
+        } else {
+            let recovery = (delta - self.duck_hold).as_secs_f32() / DUCK_RECOVERY.as_secs_f32();
+            self.duck_hold = Duration::ZERO;
+            self.duck = (self.duck + recovery * (1.0 - DUCK_LEVEL)).min(1.0);
+        }
+    }
+
+    /// Tracks that should be playing, fading in or out.
+    pub fn tracks(&self) -> impl Iterator<Item = Track> + '_ {
+        self.fades.iter().map(|&(track, _)| track)
+    }
+
+    /// Volume of `track`, before the music bus.
+    pub fn volume(&self, track: Track) -> f32 {
+        self.fades
+            .iter()
+            .find(|&&(fading, _)| fading == track)
+            .map_or(0.0, |&(_, fade)| fade * self.duck)
+    }
+}
+
+#[derive(Resource)]
+pub struct MusicTracks(HashMap<Track, Beep>);
+
+pub struct MusicPlugin;
+
+impl Plugin for MusicPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MusicMix>()
+            .add_startup_system(setup_music)
+            .add_system(play_music);
+    }
+}
+
+pub fn setup_music(mut commands: Commands) {
+    let tracks = Track::ALL
+        .into_iter()
+        .map(|track| (track, Beep::new(&track.notes())))
+        .collect();
+
+    commands.insert_resource(MusicTracks(tracks));
+}
+
+/// Follows the state with the music, starting and stopping the looping
+/// sinks as tracks fade in and out.
+pub fn play_music(
+    state: Res<State<GameState>>,
+    collision_events: EventReader<CollisionEvent>,
+    mut game_over: Local<bool>,
+    mut sinks: Local<HashMap<Track, Handle<AudioSink>>>,
+    mut mix: ResMut<MusicMix>,
+    tracks: Res<MusicTracks>,
+    timer: Res<Time>,
+    settings: Res<Settings>,
+    mute: Res<MuteOverride>,
+    audio: Res<Audio<Panned<Beep>>>,
+    mut beeps: ResMut<Assets<Panned<Beep>>>,
+    audio_sinks: Res<Assets<AudioSink>>,
+) {
+    let state = state.current();
+    if !collision_events.is_empty() {
+        collision_events.clear();
+        *game_over = true;
+    }
+    if matches!(state, GameState::Playing | GameState::Demo) {
+        *game_over = false;
+    }
+
+    mix.play(Track::for_state(state, *game_over));
+    mix.update(timer.delta());
+
+    sinks.retain(|track, sink| {
+        let playing = mix.tracks().any(|fading| fading == *track);
+        if !playing {
+            if let Some(sink) = audio_sinks.get(sink) {
+                sink.stop();
+            }
+        }
+        playing
+    });
+
+    for track in mix.tracks() {
+        let volume = mix.volume(track) * settings.mixer.gain(Bus::Music, *mute);
+        match sinks.get(&track) {
+            Some(sink) => {
+                if let Some(sink) = audio_sinks.get(sink) {
+                    sink.set_volume(volume);
+                }
+            }
+            None => {
+                let music = beeps.add(Panned::new(tracks.0[&track].clone(), Pan::new(0.0)));
+                let sink =
+                    audio.play_with_settings(music, PlaybackSettings::LOOP.with_volume(volume));
+                sinks.insert(track, audio_sinks.get_handle(sink));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: Duration = Duration::from_millis(100);
+
+    #[test]
+    fn tracks_follow_the_state() {
+        assert_eq!(Track::for_state(&GameState::Menu, false), Track::Menu);
+        assert_eq!(Track::for_state(&GameState::Menu, true), Track::GameOver);
+        assert_eq!(
+            Track::for_state(&GameState::Settings, true),
+            Track::GameOver
+        );
+        assert_eq!(Track::for_state(&GameState::Paused, true), Track::Race);
+        assert_eq!(Track::for_state(&GameState::Demo, false), Track::Menu);
+    }
+
+    #[test]
+    fn tracks_crossfade() {
+        let mut mix = MusicMix::default();
+        mix.play(Track::Menu);
+        for _ in 0..10 {
+            mix.update(FRAME);
+        }
+        assert!((mix.volume(Track::Menu) - 1.0).abs() < 1e-5);
+
+        mix.play(Track::Race);
+        for _ in 0..4 {
+            mix.update(FRAME);
+        }
+        assert!((mix.volume(Track::Menu) - 0.6).abs() < 1e-5);
+        assert!((mix.volume(Track::Race) - 0.4).abs() < 1e-5);
+
+        for _ in 0..6 {
+            mix.update(FRAME);
+        }
+        assert_eq!(mix.tracks().collect::<Vec<_>>(), [Track::Race]);
+        assert!((mix.volume(Track::Race) - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn ducking_holds_then_recovers() {
+        let mut mix = MusicMix::default();
+        mix.play(Track::Race);
+        for _ in 0..10 {
+            mix.update(FRAME);
+        }
+
+        mix.duck();
+        for _ in 0..9 {
+            mix.update(FRAME);
+        }
+        assert_eq!(mix.volume(Track::Race), DUCK_LEVEL);
+
+        for _ in 0..7 {
+            mix.update(FRAME);
+        }
+        assert!((mix.volume(Track::Race) - 1.0).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,448 @@
+use crate::prelude::*;
+use bevy::input::touch::Touches;
+use std::collections::HashMap;
+
+/// Horizontal travel that turns a press into a swipe. Every further
+/// `SWIPE_DISTANCE` of the same drag changes one more lane.
+pub const SWIPE_DISTANCE: f32 = 40.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointerZone {
+    Left,
+    Right,
+    Panel,
+}
+
+impl PointerZone {
+    /// Which part of the window a world-space `x` falls on: the left or right
+    /// half of the playfield, or the side panel past its right edge.
+    pub fn at(lanes: usize, x: f32) -> Self {
+        let width = TileScreen::screen_width(lanes) as f32 * TILE_SIZE;
+
+        if x >= SCREEN_X + width {
+            Self::Panel
+        } else if x < SCREEN_X + width / 2.0 {
+            Self::Left
+        } else {
+            Self::Right
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Pointer {
+    Mouse,
+    Touch(u64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointerPhase {
+    Pressed,
+    Moved,
+    Released,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointerAction {
+    Steer(Steer),
+    Boost(bool),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Gesture {
+    start: f32,
+    swiped: bool,
+    boosting: bool,
+}
+
+/// Turns presses, drags and releases of the mouse and every finger into lane
+/// changes and boosts. Positions are world-space `x` coordinates.
+#[derive(Resource, Debug, Default)]
+pub struct Gestures {
+    active: HashMap<Pointer, Gesture>,
+    /// Set for the frame a run starts in, so the tap on the menu's Play
+    /// button does not also steer.
+    pub ignore_presses: bool,
+}
+
+impl Gestures {
+    pub fn update(
+        &mut self,
+        lanes: usize,
+        pointer: Pointer,
+        phase: PointerPhase,
+        x: f32,
+    ) -> Option<PointerAction> {
+        let swipe = |start: f32| match x - start {
+            travel if travel <= -SWIPE_DISTANCE => Some(Steer::Left),
+            travel if travel >= SWIPE_DISTANCE => Some(Steer::Right),
+            _ => None,
+        };
+
+        match phase {
+            PointerPhase::Pressed => {
+                let boosting = PointerZone::at(lanes, x) == PointerZone::Panel;
+                self.active.insert(
+                    pointer,
+                    Gesture {
+                        start: x,
+                        swiped: false,
+                        boosting,
+                    },
+                );
+                boosting.then_some(PointerAction::Boost(true))
+            }
+            PointerPhase::Moved => {
+                let gesture = self.active.get_mut(&pointer)?;
+                if gesture.boosting {
+                    return None;
+                }
+
+                let steer = swipe(gesture.start)?;
+                gesture.start = x;
+                gesture.swiped = true;
+                Some(PointerAction::Steer(steer))
+            }
+            PointerPhase::Released | PointerPhase::Cancelled => {
+                let gesture = self.active.remove(&pointer)?;
+                if gesture.boosting {
+                    return (!self.is_boosting()).then_some(PointerAction::Boost(false));
+                }
+                if phase == PointerPhase::Cancelled {
+                    return None;
+                }
+
+                if let Some(steer) = swipe(gesture.start) {
+                    return Some(PointerAction::Steer(steer));
+                }
+                if gesture.swiped {
+                    return None;
+                }
+
+                match PointerZone::at(lanes, gesture.start) {
+                    PointerZone::Left => Some(PointerAction::Steer(Steer::Left)),
+                    PointerZone::Right => Some(PointerAction::Steer(Steer::Right)),
+                    PointerZone::Panel => None,
+                }
+            }
+        }
+    }
+
+    fn is_boosting(&self) -> bool {
+        self.active.values().any(|gesture| gesture.boosting)
+    }
+
+    pub fn clear(&mut self) {
+        self.active.clear();
+        self.ignore_presses = true;
+    }
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TouchButton {
+    Left,
+    Right,
+    Boost,
+}
+
+/// Root node of the on-screen buttons, hidden until the first touch.
+#[derive(Component)]
+pub struct TouchControls;
+
+pub fn setup_touch_controls(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    strings: Res<Strings>,
+) {
+    let text_style = TextStyle {
+        font: asset_server.load("fonts/Calculator.ttf"),
+        font_size: 20.0,
+        ..default()
+    };
+    let button = |width: f32| {
+        (
+            ButtonBundle {
+                style: Style {
+                    size: Size::new(Val::Px(width), Val::Px(40.0)),
+                    margin: UiRect::all(Val::Px(2.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            Themed::Button,
+        )
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    display: Display::None,
+                    position_type: PositionType::Absolute,
+                    flex_direction: FlexDirection::ColumnReverse,
+                    position: UiRect {
+                        bottom: Val::Px(WINDOW_PADDING),
+                        right: Val::Px(WINDOW_PADDING),
+                        ..default()
+                    },
+                    ..default()
+                },
+                background_color: Color::NONE.into(),
+                ..default()
+            },
+            TouchControls,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((button(84.0), TouchButton::Boost))
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(strings.get("touch.boost"), text_style.clone()),
+                        Themed::ButtonText,
+                        Localized("touch.boost"),
+                    ));
+                });
+
+            parent
+                .spawn(NodeBundle {
+                    background_color: Color::NONE.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for (touch_button, label) in
+                        [(TouchButton::Left, "L"), (TouchButton::Right, "R")]
+                    {
+                        parent
+                            .spawn((button(40.0), touch_button))
+                            .with_children(|parent| {
+                                parent.spawn((
+                                    TextBundle::from_section(label, text_style.clone()),
+                                    Themed::ButtonText,
+                                ));
+                            });
+                    }
+                });
+        });
+}
+
+pub fn show_touch_controls(
+    touches: Res<Touches>,
+    mut controls_query: Query<&mut Style, With<TouchControls>>,
+) {
+    if !touches.any_just_pressed() {
+        return;
+    }
+
+    for mut style in &mut controls_query {
+        style.display = Display::Flex;
+    }
+}
+
+pub fn reset_gestures(mut gestures: ResMut<Gestures>) {
+    gestures.clear();
+}
+
+pub fn pointer_player(
+    windows: Res<Windows>,
+    layout: Res<ScreenLayout>,
+    mouse_input: Res<Input<MouseButton>>,
+    touches: Res<Touches>,
+    buttons_query: Query<&Interaction, With<TouchButton>>,
+    mut gestures: ResMut<Gestures>,
+    mut steering: ResMut<SteerQueue>,
+    mut race: ResMut<Race>,
+) {
+    let Some(window) = windows.get_primary() else {
+        return;
+    };
+    let lanes = race.config.lanes;
+
+    let mut events = Vec::new();
+    if let Some(cursor) = window.cursor_position() {
+        if mouse_input.just_pressed(MouseButton::Left) {
+            events.push((Pointer::Mouse, PointerPhase::Pressed, cursor.x));
+        } else if mouse_input.just_released(MouseButton::Left) {
+            events.push((Pointer::Mouse, PointerPhase::Released, cursor.x));
+        } else if mouse_input.pressed(MouseButton::Left) {
+            events.push((Pointer::Mouse, PointerPhase::Moved, cursor.x));
+        }
+    } else if mouse_input.just_released(MouseButton::Left) {
+        events.push((Pointer::Mouse, PointerPhase::Cancelled, 0.0));
+    }
+
+    for touch in touches.iter() {
+        let phase = if touches.just_pressed(touch.id()) {
+            PointerPhase::Pressed
+        } else {
+            PointerPhase::Moved
+        };
+        events.push((Pointer::Touch(touch.id()), phase, touch.position().x));
+    }
+    for touch in touches.iter_just_released() {
+        events.push((
+            Pointer::Touch(touch.id()),
+            PointerPhase::Released,
+            touch.position().x,
+        ));
+    }
+    for touch in touches.iter_just_cancelled() {
+        events.push((
+            Pointer::Touch(touch.id()),
+            PointerPhase::Cancelled,
+            touch.position().x,
+        ));
+    }
+
+    // Presses on the on-screen buttons are handled by `touch_buttons`.
+    let on_button = buttons_query
+        .iter()
+        .any(|interaction| *interaction == Interaction::Clicked);
+    let ignore_presses = std::mem::take(&mut gestures.ignore_presses) || on_button;
+
+    for (pointer, phase, x) in events {
+        if phase == PointerPhase::Pressed && ignore_presses {
+            continue;
+        }
+
+        let x = layout.window_to_world_x(window.width(), lanes, x);
+        match gestures.update(lanes, pointer, phase, x) {
+            Some(PointerAction::Steer(steer)) => steering.press(steer),
+            Some(PointerAction::Boost(boosting)) => race.is_boosting = boosting,
+            None => {}
+        }
+    }
+}
+
+pub fn touch_buttons(
+    mut steering: ResMut<SteerQueue>,
+    mut race: ResMut<Race>,
+    theme: Res<Theme>,
+    mut buttons_query: Query<
+        (&Interaction, &TouchButton, &mut BackgroundColor),
+        Changed<Interaction>,
+    >,
+    held_query: Query<(&Interaction, &TouchButton)>,
+) {
+    for (interaction, button, mut color) in &mut buttons_query {
+        let clicked = *interaction == Interaction::Clicked;
+        match button {
+            TouchButton::Left if clicked => steering.press(Steer::Left),
+            TouchButton::Right if clicked => steering.press(Steer::Right),
+            TouchButton::Boost => race.is_boosting = clicked,
+            _ => {}
+        }
+
+        *color = match interaction {
+            Interaction::Clicked => theme.button_pressed.into(),
+            Interaction::Hovered => theme.button_hovered.into(),
+            Interaction::None => theme.button.into(),
+        };
+    }
+
+    for (interaction, button) in &held_query {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+
+        match button {
+            TouchButton::Left => steering.hold(Steer::Left),
+            TouchButton::Right => steering.hold(Steer::Right),
+            TouchButton::Boost => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LANES: usize = 3;
+    const LEFT: f32 = SCREEN_X + TILE_SIZE;
+    const RIGHT: f32 = SCREEN_X + 12.0 * TILE_SIZE;
+    const PANEL: f32 = SCREEN_X + 14.0 * TILE_SIZE;
+
+    #[test]
+    fn zones_split_the_playfield_in_halves() {
+        assert_eq!(PointerZone::at(LANES, LEFT), PointerZone::Left);
+        assert_eq!(PointerZone::at(LANES, RIGHT), PointerZone::Right);
+        assert_eq!(PointerZone::at(LANES, PANEL), PointerZone::Panel);
+        assert_eq!(
+            PointerZone::at(5, PANEL),
+            PointerZone::Right,
+            "extra lanes widen the playfield"
+        );
+    }
+
+    #[test]
+    fn taps_steer_towards_their_half() {
+        let mut gestures = Gestures::default();
+        let mut tap = |x| {
+            gestures.update(LANES, Pointer::Mouse, PointerPhase::Pressed, x);
+            gestures.update(LANES, Pointer::Mouse, PointerPhase::Released, x)
+        };
+
+        assert_eq!(tap(LEFT), Some(PointerAction::Steer(Steer::Left)));
+        assert_eq!(tap(RIGHT), Some(PointerAction::Steer(Steer::Right)));
+    }
+
+    #[test]
+    fn swipes_steer_once_per_distance() {
+        let mut gestures = Gestures::default();
+        let finger = Pointer::Touch(7);
+        let mut update = |phase, x| gestures.update(LANES, finger, phase, x);
+
+        assert_eq!(update(PointerPhase::Pressed, RIGHT), None);
+        assert_eq!(update(PointerPhase::Moved, RIGHT - 10.0), None);
+        assert_eq!(
+            update(PointerPhase::Moved, RIGHT - SWIPE_DISTANCE),
+            Some(PointerAction::Steer(Steer::Left))
+        );
+        assert_eq!(
+            update(PointerPhase::Moved, RIGHT - SWIPE_DISTANCE - 10.0),
+            None
+        );
+        assert_eq!(
+            update(PointerPhase::Moved, RIGHT - SWIPE_DISTANCE * 2.0),
+            Some(PointerAction::Steer(Steer::Left))
+        );
+        assert_eq!(
+            update(PointerPhase::Released, RIGHT - SWIPE_DISTANCE * 2.0),
+            None
+        );
+    }
+
+    #[test]
+    fn fast_swipe_steers_on_release() {
+        let mut gestures = Gestures::default();
+
+        gestures.update(LANES, Pointer::Mouse, PointerPhase::Pressed, LEFT);
+        assert_eq!(
+            gestures.update(LANES, Pointer::Mouse, PointerPhase::Released, RIGHT),
+            Some(PointerAction::Steer(Steer::Right))
+        );
+    }
+
+    #[test]
+    fn holding_the_panel_boosts_until_every_finger_lifts() {
+        let mut gestures = Gestures::default();
+        let mut update = |id, phase, x| gestures.update(LANES, Pointer::Touch(id), phase, x);
+
+        assert_eq!(
+            update(1, PointerPhase::Pressed, PANEL),
+            Some(PointerAction::Boost(true))
+        );
+        assert_eq!(
+            update(2, PointerPhase::Pressed, PANEL),
+            Some(PointerAction::Boost(true))
+        );
+        assert_eq!(update(1, PointerPhase::Moved, LEFT), None);
+        assert_eq!(update(1, PointerPhase::Released, LEFT), None);
+        assert_eq!(
+            update(2, PointerPhase::Cancelled, PANEL),
+            Some(PointerAction::Boost(false))
+        );
+    }
+}
@@ -0,0 +1,366 @@
+use crate::prelude::*;
+use rand::rngs::StdRng;
+use std::ops::RangeInclusive;
+use std::time::Duration;
+
+pub const LANES: usize = 3;
+pub const MAX_LANES: usize = 5;
+/// Lane counts accepted everywhere a run is set up, from the menu and the
+/// command lines to loaded replays.
+pub const LANE_RANGE: RangeInclusive<usize> = 2..=MAX_LANES;
+pub const MAX_LEVEL: usize = 50;
+pub const ENEMY_COUNT: usize = 4;
+pub const WALL_COUNT: usize = 6;
+
+pub const STEP_SECONDS: f32 = 0.08;
+pub const SCORE_SECONDS: f32 = 1.0;
+pub const SCORE_POINTS: usize = 100;
+pub const LEVEL_POINTS: usize = 1000;
+pub const LEVEL_SPEEDUP: f32 = 0.02;
+pub const BOOST_FACTOR: f32 = 2.0;
+
+/// Rows, relative to the player, where a rival in the same lane is a hit.
+pub const HIT_ROWS: RangeInclusive<i32> = -4..=3;
+
+const ENEMY_SPAWN_ROW: i32 = SCREEN_HEIGHT as i32 + CAR_SPACING as i32 - 2;
+const ENEMY_DESPAWN_ROW: i32 = -(CAR_SPACING as i32);
+const WALL_SPAWN_ROW: i32 = SCREEN_HEIGHT as i32 + WALL_SPACING as i32 - 1;
+const WALL_DESPAWN_ROW: i32 = -(WALL_SPACING as i32) - 1;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Steer {
+    #[default]
+    Straight,
+    Left,
+    Right,
+}
+
+/// An enemy car. `row` is the bottom row of its sprite, the player sits at row 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rival {
+    pub lane: usize,
+    pub row: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CrashCause {
+    /// A rival came down the player's lane.
+    Rammed,
+    /// The player steered into a rival.
+    Swerved,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Crash {
+    pub cause: CrashCause,
+    pub lane: usize,
+    pub boosting: bool,
+}
+
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+pub struct RaceConfig {
+    pub lanes: usize,
+    pub level: usize,
+    pub seed: u64,
+}
+
+impl Default for RaceConfig {
+    fn default() -> Self {
+        Self {
+            lanes: LANES,
+            level: 0,
+            seed: thread_rng().gen(),
+        }
+    }
+}
+
+/// The rules of a run, kept in tile rows and lanes so they can be played
+/// without rendering anything.
+#[derive(Resource, Clone)]
+pub struct Race {
+    pub config: RaceConfig,
+    pub lane: usize,
+    pub rivals: Vec<Rival>,
+    pub walls: Vec<i32>,
+    pub score: usize,
+    pub speed_factor: f32,
+    pub is_boosting: bool,
+    pub distance: usize,
+    pub elapsed: Duration,
+    pub crash: Option<Crash>,
+    /// Inputs of this run so far, when it is being recorded.
+    pub replay: Option<Replay>,
+    move_timer: Timer,
+    score_timer: Timer,
+    swerved: bool,
+    rng: StdRng,
+}
+
+impl Race {
+    pub fn new(config: RaceConfig) -> Self {
+        let mut rng = StdRng::seed_from_u64(config.seed);
+
+        let rivals = (0..ENEMY_COUNT)
+            .map(|i| Rival {
+                lane: rng.gen_range(0..config.lanes),
+                row: ENEMY_SPAWN_ROW + i as i32 * CAR_SPACING as i32,
+            })
+            .collect();
+
+        let walls = (0..WALL_COUNT)
+            .map(|i| i as i32 * WALL_SPACING as i32)
+            .collect();
+
+        Self {
+            config,
+            lane: config.lanes / 2,
+            rivals,
+            walls,
+            score: 0,
+            speed_factor: Self::speed_for(config.level, 0),
+            is_boosting: false,
+            distance: 0,
+            elapsed: Duration::ZERO,
+            crash: None,
+            replay: None,
+            move_timer: Timer::from_seconds(STEP_SECONDS, TimerMode::Repeating),
+            score_timer: Timer::from_seconds(SCORE_SECONDS, TimerMode::Repeating),
+            swerved: false,
+            rng,
+        }
+    }
+
+    /// Starts a fresh run with the same settings, seeded from this one.
+    pub fn restart(&mut self) {
+        let seed = self.rng.gen();
+        let recording = self.replay.is_some();
+
+        *self = Self::new(RaceConfig {
+            seed,
+            ..self.config
+        });
+        if recording {
+            self.record();
+        }
+    }
+
+    /// Starts over with a new lane count and starting level.
+    pub fn configure(&mut self, lanes: usize, level: usize) {
+        self.config.lanes = lanes;
+        self.config.level = level;
+        self.restart();
+    }
+
+    pub fn record(&mut self) {
+        self.replay = Some(Replay::new(self.config));
+    }
+
+    pub fn speed_for(level: usize, score: usize) -> f32 {
+        (level + score / LEVEL_POINTS) as f32 * LEVEL_SPEEDUP + 1.0
+    }
+
+    /// The starting level plus one for every `LEVEL_POINTS` scored.
+    pub fn level(&self) -> usize {
+        self.config.level + self.score / LEVEL_POINTS
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.crash.is_some()
+    }
+
+    /// How fast the move timer runs compared to real time.
+    pub fn pace(&self) -> f32 {
+        if self.is_boosting {
+            self.speed_factor * BOOST_FACTOR
+        } else {
+            self.speed_factor
+        }
+    }
+
+    pub fn move_timer(&self) -> &Timer {
+        &self.move_timer
+    }
+
+    pub fn steer(&mut self, steer: Steer) -> bool {
+        if self.is_over() {
+            return false;
+        }
+
+        match steer {
+            Steer::Left if self.lane > 0 => self.lane -= 1,
+            Steer::Right if self.lane + 1 < self.config.lanes => self.lane += 1,
+            _ => return false,
+        }
+
+        self.swerved = true;
+        if let Some(replay) = &mut self.replay {
+            replay.steer(steer);
+        }
+        true
+    }
+
+    /// The closest rival that has not come on screen yet.
+    pub fn next_rival(&self) -> Option<Rival> {
+        self.rivals
+            .iter()
+            .filter(|rival| rival.row >= SCREEN_HEIGHT as i32)
+            .min_by_key(|rival| rival.row)
+            .copied()
+    }
+
+    /// Whether a rival in `lane` would hit the player once the road has
+    /// scrolled `ahead` more rows.
+    pub fn is_blocked(&self, lane: usize, ahead: i32) -> bool {
+        self.rivals
+            .iter()
+            .any(|rival| rival.lane == lane && HIT_ROWS.contains(&(rival.row - ahead)))
+    }
+
+    pub fn update(&mut self, delta: Duration) {
+        if self.is_over() {
+            return;
+        }
+
+        if let Some(replay) = &mut self.replay {
+            replay.end_frame(delta, self.is_boosting);
+        }
+
+        self.elapsed += delta;
+        self.score_timer.tick(delta);
+        for _ in 0..self.score_timer.times_finished_this_tick() {
+            self.score += SCORE_POINTS;
+            self.speed_factor = Self::speed_for(self.config.level, self.score);
+        }
+
+        self.move_timer.tick(delta.mul_f32(self.pace()));
+        if self.move_timer.finished() {
+            self.advance();
+        }
+
+        if self.is_blocked(self.lane, 0) {
+            self.crash = Some(Crash {
+                cause: if self.swerved {
+                    CrashCause::Swerved
+                } else {
+                    CrashCause::Rammed
+                },
+                lane: self.lane,
+                boosting: self.is_boosting,
+            });
+        }
+        self.swerved = false;
+    }
+
+    fn advance(&mut self) {
+        self.distance += 1;
+
+        for rival in self.rivals.iter_mut() {
+            rival.row -= 1;
+            if rival.row <= ENEMY_DESPAWN_ROW {
+                rival.row = ENEMY_SPAWN_ROW;
+                rival.lane = self.rng.gen_range(0..self.config.lanes);
+            }
+        }
+
+        for wall in self.walls.iter_mut() {
+            *wall -= 1;
+            if *wall <= WALL_DESPAWN_ROW {
+                *wall = WALL_SPAWN_ROW;
+            }
+        }
+    }
+}
+
+impl Default for Race {
+    fn default() -> Self {
+        Self::new(RaceConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: Duration = Duration::from_millis(16);
+
+    /// A race with every rival parked far up the road.
+    fn empty_road() -> Race {
+        let mut race = Race::new(RaceConfig {
+            lanes: LANES,
+            level: 0,
+            seed: 1,
+        });
+        for rival in &mut race.rivals {
+            rival.row = 1000;
+        }
+        race
+    }
+
+    fn drive_straight(race: &mut Race) {
+        while !race.is_over() && race.elapsed < Duration::from_secs(120) {
+            race.update(FRAME);
+        }
+    }
+
+    #[test]
+    fn a_seed_plays_the_same_run_every_time() {
+        let config = RaceConfig {
+            lanes: 4,
+            level: 5,
+            seed: 42,
+        };
+        let (mut first, mut second) = (Race::new(config), Race::new(config));
+        drive_straight(&mut first);
+        drive_straight(&mut second);
+
+        assert!(first.is_over());
+        assert_eq!(
+            (first.elapsed, first.score, first.distance, first.crash),
+            (second.elapsed, second.score, second.distance, second.crash)
+        );
+    }
+
+    #[test]
+    fn crashes_record_their_cause() {
+        let mut rammed = empty_road();
+        rammed.rivals[0] = Rival {
+            lane: rammed.lane,
+            row: 6,
+        };
+        drive_straight(&mut rammed);
+        assert_eq!(
+            rammed.crash,
+            Some(Crash {
+                cause: CrashCause::Rammed,
+                lane: LANES / 2,
+                boosting: false,
+            })
+        );
+
+        let mut swerved = empty_road();
+        swerved.rivals[0] = Rival {
+            lane: swerved.lane + 1,
+            row: 0,
+        };
+        assert!(swerved.steer(Steer::Right));
+        swerved.update(FRAME);
+        assert_eq!(
+            swerved.crash.map(|crash| crash.cause),
+            Some(CrashCause::Swerved)
+        );
+        assert!(!swerved.steer(Steer::Left), "no steering after a crash");
+    }
+
+    #[test]
+    fn score_raises_the_level_and_speed() {
+        let mut race = empty_road();
+        race.rivals.clear();
+        race.update(Duration::from_secs_f32(SCORE_SECONDS) * 10);
+
+        assert_eq!(race.score, LEVEL_POINTS);
+        assert_eq!(race.level(), 1);
+        assert_eq!(race.speed_factor, 1.0 + LEVEL_SPEEDUP);
+    }
+}
@@ -0,0 +1,204 @@
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Where the game keeps the last finished run when no `--record` file is
+/// given, for `export` to pick up.
+pub const LAST_RUN_FILE: &str = "last_run.json";
+
+/// Everything the player did during one frame of a run.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReplayFrame {
+    pub nanos: u64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub steer: Vec<Steer>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub boost: bool,
+}
+
+/// A recorded run. The rules are deterministic for a seed, so the inputs and
+/// frame times are enough to play it back exactly.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub config: RaceConfig,
+    pub frames: Vec<ReplayFrame>,
+    #[serde(skip)]
+    current: ReplayFrame,
+}
+
+impl Replay {
+    pub fn new(config: RaceConfig) -> Self {
+        Self {
+            config,
+            frames: Vec::new(),
+            current: ReplayFrame::default(),
+        }
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let replay: Self = serde_json::from_str(&fs::read_to_string(path)?)?;
+        if !LANE_RANGE.contains(&replay.config.lanes) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("replay has {} lanes", replay.config.lanes),
+            ));
+        }
+
+        Ok(replay)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string(self)?)
+    }
+
+    pub fn steer(&mut self, steer: Steer) {
+        self.current.steer.push(steer);
+    }
+
+    pub fn end_frame(&mut self, delta: Duration, boost: bool) {
+        let mut frame = std::mem::take(&mut self.current);
+        frame.nanos = delta.as_nanos() as u64;
+        frame.boost = boost;
+        self.frames.push(frame);
+    }
+
+    /// Feeds one recorded frame to the race.
+    pub fn apply(frame: &ReplayFrame, race: &mut Race) {
+        for &steer in &frame.steer {
+            race.steer(steer);
+        }
+        race.is_boosting = frame.boost;
+        race.update(Duration::from_nanos(frame.nanos));
+    }
+
+    /// Plays the whole replay without rendering and returns where it ended.
+    pub fn play(&self) -> Race {
+        let mut race = Race::new(self.config);
+        for frame in &self.frames {
+            Self::apply(frame, &mut race);
+        }
+
+        race
+    }
+}
+
+/// Replay being shown in `GameState::Replay`.
+#[derive(Resource)]
+pub struct Playback {
+    pub replay: Replay,
+    pub frame: usize,
+}
+
+/// Where to save the replay of every finished run.
+#[derive(Resource)]
+pub struct ReplayRecorder {
+    pub path: PathBuf,
+}
+
+pub fn play_replay(mut playback: ResMut<Playback>, mut race: ResMut<Race>) {
+    let Playback { replay, frame } = &mut *playback;
+
+    if let Some(recorded) = replay.frames.get(*frame) {
+        Replay::apply(recorded, &mut race);
+        *frame += 1;
+    }
+}
+
+/// Leaves a replay that ran out of frames without crashing; crashes go
+/// through `check_collisions` like a normal run.
+pub fn end_replay(
+    mut commands: Commands,
+    playback: Res<Playback>,
+    mut race: ResMut<Race>,
+    mut state: ResMut<State<GameState>>,
+    entities_query: Query<Entity, Or<(With<Car>, With<Wall>)>>,
+) {
+    if race.is_over() || playback.frame < playback.replay.frames.len() {
+        return;
+    }
+
+    for entity in &entities_query {
+        commands.entity(entity).despawn_recursive();
+    }
+    race.restart();
+    state.set(GameState::Menu).unwrap();
+}
+
+pub fn cleanup_replay(mut commands: Commands) {
+    commands.remove_resource::<Playback>();
+}
+
+pub fn save_replay(race: Res<Race>, recorder: Option<Res<ReplayRecorder>>) {
+    let (Some(replay), Some(recorder)) = (&race.replay, recorder) else {
+        return;
+    };
+
+    if race.is_over() {
+        if let Err(err) = replay.save(&recorder.path) {
+            error!(
+                "Could not save replay to {}: {}",
+                recorder.path.display(),
+                err
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saved_runs_play_back_exactly() {
+        let mut race = Race::new(RaceConfig {
+            lanes: 4,
+            level: 3,
+            seed: 11,
+        });
+        race.record();
+        let mut bot = Bot::new(Policy::Random, 11);
+        let delta = Duration::from_millis(16);
+        for frame in 0.. {
+            if race.is_over() {
+                break;
+            }
+            race.steer(bot.decide(&race, delta));
+            race.is_boosting = frame % 50 < 10;
+            // Uneven frame times, like a real window.
+            race.update(delta + Duration::from_micros(frame % 7 * 300));
+        }
+
+        let path = std::env::temp_dir().join("bevy_retro_racing_replay_test.json");
+        race.replay.as_ref().unwrap().save(&path).unwrap();
+        let loaded = Replay::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        let played = loaded.unwrap().play();
+        assert!(race.crash.is_some());
+        assert_eq!(
+            (played.elapsed, played.score, played.distance, played.crash),
+            (race.elapsed, race.score, race.distance, race.crash)
+        );
+    }
+
+    #[test]
+    fn replays_outside_the_lane_range_are_rejected() {
+        let path = std::env::temp_dir().join("bevy_retro_racing_bad_replay_test.json");
+        let replay = Replay::new(RaceConfig {
+            lanes: MAX_LANES + 1,
+            level: 0,
+            seed: 0,
+        });
+        replay.save(&path).unwrap();
+        let loaded = Replay::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,213 @@
+use crate::prelude::*;
+
+/// Tiles across one digit.
+pub const DIGIT_WIDTH: usize = 3;
+/// Tiles down one digit.
+pub const DIGIT_HEIGHT: usize = 5;
+/// Blank tile columns between digits.
+pub const DIGIT_GAP: usize = 1;
+/// Screen size of a readout tile.
+pub const SEGMENT_TILE: f32 = 5.0;
+
+/// The seven segments of a digit, in the usual `a` to `g` order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Segment {
+    Top,
+    UpperRight,
+    LowerRight,
+    Bottom,
+    LowerLeft,
+    UpperLeft,
+    Middle,
+}
+
+impl Segment {
+    pub const ALL: [Self; 7] = [
+        Self::Top,
+        Self::UpperRight,
+        Self::LowerRight,
+        Self::Bottom,
+        Self::LowerLeft,
+        Self::UpperLeft,
+        Self::Middle,
+    ];
+
+    /// Tiles of the segment as (column, row) with row 0 at the top. Segments
+    /// share the corner tiles where they meet.
+    pub fn tiles(self) -> [(usize, usize); 3] {
+        match self {
+            Self::Top => [(0, 0), (1, 0), (2, 0)],
+            Self::UpperRight => [(2, 0), (2, 1), (2, 2)],
+            Self::LowerRight => [(2, 2), (2, 3), (2, 4)],
+            Self::Bottom => [(0, 4), (1, 4), (2, 4)],
+            Self::LowerLeft => [(0, 2), (0, 3), (0, 4)],
+            Self::UpperLeft => [(0, 0), (0, 1), (0, 2)],
+            Self::Middle => [(0, 2), (1, 2), (2, 2)],
+        }
+    }
+
+    /// Whether `digit` lights this segment.
+    pub fn lit_in(self, digit: u8) -> bool {
+        // Bit n is `Segment::ALL[n]`.
+        const DIGITS: [u8; 10] = [
+            0b0111111, 0b0000110, 0b1011011, 0b1001111, 0b1100110, 0b1101101, 0b1111101, 0b0000111,
+            0b1111111, 0b1101111,
+        ];
+
+        DIGITS[digit as usize] & (1 << self as u8) != 0
+    }
+}
+
+/// Tile columns taken by a readout of `digits` digits.
+pub fn readout_width(digits: usize) -> usize {
+    digits * (DIGIT_WIDTH + DIGIT_GAP) - DIGIT_GAP
+}
+
+/// `value` right-aligned in `digits` places, with blanks in front. Values
+/// that do not fit show as all nines, like the handheld's counter.
+pub fn readout_digits(value: usize, digits: usize) -> Vec<Option<u8>> {
+    let max = 10usize.saturating_pow(digits as u32) - 1;
+    let text = value.min(max).to_string();
+
+    let mut places = vec![None; digits - text.len()];
+    places.extend(text.bytes().map(|byte| Some(byte - b'0')));
+    places
+}
+
+/// The tile at column `x` and row `y` (0 at the top) of a readout: `None`
+/// off every segment, otherwise whether it is lit. Blank places keep their
+/// unlit segments.
+pub fn readout_cell(digits: &[Option<u8>], x: usize, y: usize) -> Option<bool> {
+    let (place, column) = (x / (DIGIT_WIDTH + DIGIT_GAP), x % (DIGIT_WIDTH + DIGIT_GAP));
+    let digit = *digits.get(place)?;
+
+    Segment::ALL
+        .iter()
+        .filter(|segment| segment.tiles().contains(&(column, y)))
+        .map(|segment| digit.is_some_and(|digit| segment.lit_in(digit)))
+        .reduce(|lit, also| lit || also)
+}
+
+/// Which number a readout shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScoreValue {
+    Score,
+    Highscore,
+    Speed,
+}
+
+/// A number drawn as seven-segment tiles in the side panel.
+#[derive(Component)]
+pub struct SegmentReadout {
+    pub shows: ScoreValue,
+    pub digits: usize,
+    pub value: usize,
+    image: Handle<Image>,
+}
+
+/// Spawns a hidden readout with its top right corner at `top`, `right` in
+/// its parent.
+pub fn spawn_segment_readout(
+    commands: &mut Commands,
+    images: &mut Assets<Image>,
+    shows: ScoreValue,
+    digits: usize,
+    top: f32,
+    right: f32,
+) -> Entity {
+    let width = readout_width(digits);
+    let image = images.add(tile_image(width, DIGIT_HEIGHT));
+
+    commands
+        .spawn((
+            ImageBundle {
+                style: Style {
+                    display: Display::None,
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        top: Val::Px(top),
+                        right: Val::Px(right),
+                        ..default()
+                    },
+                    size: Size::new(
+                        Val::Px(width as f32 * SEGMENT_TILE),
+                        Val::Px(DIGIT_HEIGHT as f32 * SEGMENT_TILE),
+                    ),
+                    ..default()
+                },
+                image: image.clone().into(),
+                ..default()
+            },
+            SegmentReadout {
+                shows,
+                digits,
+                value: 0,
+                image,
+            },
+        ))
+        .id()
+}
+
+/// Redraws readouts whose number or colors changed.
+pub fn draw_segment_readouts(
+    theme: Res<Theme>,
+    mut images: ResMut<Assets<Image>>,
+    readouts: Query<(&SegmentReadout, ChangeTrackers<SegmentReadout>)>,
+) {
+    for (readout, changes) in &readouts {
+        if !theme.is_changed() && !changes.is_changed() {
+            continue;
+        }
+        let Some(image) = images.get_mut(&readout.image) else {
+            continue;
+        };
+
+        let digits = readout_digits(readout.value, readout.digits);
+        let width = readout_width(readout.digits);
+        rasterize_cells(&mut image.data, width, DIGIT_HEIGHT, &theme, |x, y| {
+            readout_cell(&digits, x, DIGIT_HEIGHT - 1 - y).map(|lit| lit as u8 as f32)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A readout as text: `#` lit, `o` unlit, `.` off the segments.
+    fn draw(value: usize, digits: usize) -> Vec<String> {
+        let places = readout_digits(value, digits);
+        (0..DIGIT_HEIGHT)
+            .map(|y| {
+                (0..readout_width(digits))
+                    .map(|x| match readout_cell(&places, x, y) {
+                        Some(true) => '#',
+                        Some(false) => 'o',
+                        None => '.',
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn digits_light_their_segments() {
+        assert_eq!(draw(7, 1), ["###", "o.#", "oo#", "o.#", "oo#"]);
+        assert_eq!(draw(8, 1), ["###", "#.#", "###", "#.#", "###"]);
+        assert_eq!(
+            draw(42, 2),
+            ["#o#.###", "#.#.o.#", "###.###", "o.#.#.o", "oo#.###"]
+        );
+    }
+
+    #[test]
+    fn numbers_are_right_aligned_and_capped() {
+        assert_eq!(readout_digits(42, 4), [None, None, Some(4), Some(2)]);
+        assert_eq!(readout_digits(0, 2), [None, Some(0)]);
+        assert_eq!(readout_digits(123456, 3), [Some(9); 3]);
+
+        // Blank places still show their unlit segments.
+        assert_eq!(draw(1, 2)[0], "ooo.oo#");
+        assert_eq!(readout_width(5), 19);
+    }
+}
@@ -0,0 +1,566 @@
+use crate::prelude::*;
+use bevy::diagnostic::{Diagnostics, FrameTimeDiagnosticsPlugin};
+use bevy::window::WindowMode;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
+
+pub const SETTINGS_FILE: &str = "settings.ron";
+pub const VOLUME_STEP: f32 = 0.1;
+/// Window scales to pick from, `None` follows the monitor.
+pub const WINDOW_SCALES: [Option<f64>; 5] = [None, Some(1.0), Some(1.5), Some(2.0), Some(3.0)];
+
+/// A window scale from `--scale` for this session only. Kept apart from
+/// `Settings` so it is never saved.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq)]
+pub struct ScaleOverride(pub Option<f64>);
+
+/// User preferences, read before the app starts. Fields missing from the
+/// file keep their defaults.
+#[derive(Resource, Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub mixer: Mixer,
+    pub theme: ThemePreset,
+    pub language: Language,
+    pub window_scale: Option<f64>,
+    pub fullscreen: bool,
+    pub show_fps: bool,
+    /// Lets lit cells fade out like a slow LCD.
+    pub ghosting: bool,
+    /// Draws the scores as seven-segment tiles instead of text.
+    pub segment_digits: bool,
+    /// How a held steer repeats.
+    pub repeat: AutoRepeat,
+}
+
+impl Settings {
+    /// Reads the settings file. Values out of range, as a hand-edited file
+    /// may have, are brought back in.
+    pub fn load(path: &Path) -> Self {
+        let mut settings: Self = read_ron(path).unwrap_or_default();
+        settings.sanitize();
+        settings
+    }
+
+    fn sanitize(&mut self) {
+        for bus in [Bus::Master, Bus::Effects, Bus::Engine, Bus::Music] {
+            let volume = self.mixer.volume_mut(bus);
+            *volume = if volume.is_nan() {
+                Mixer::default().volume(bus)
+            } else {
+                volume.clamp(0.0, 1.0)
+            };
+        }
+        if !WINDOW_SCALES.contains(&self.window_scale) {
+            self.window_scale = None;
+        }
+        self.repeat = self.repeat.clamped();
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        write_ron(path, self)
+    }
+
+    pub fn load_user() -> Self {
+        config_path(SETTINGS_FILE)
+            .map(|path| Self::load(&path))
+            .unwrap_or_default()
+    }
+
+    pub fn save_user(&self) {
+        let Some(path) = config_path(SETTINGS_FILE) else {
+            return;
+        };
+
+        if let Err(err) = self.save(&path) {
+            error!("Could not save {}: {}", path.display(), err);
+        }
+    }
+
+    /// The window scale in use: the session's, else the saved one.
+    pub fn window_scale_with(&self, session: ScaleOverride) -> Option<f64> {
+        session.0.or(self.window_scale)
+    }
+
+    pub fn window_mode(&self) -> WindowMode {
+        if self.fullscreen {
+            WindowMode::BorderlessFullscreen
+        } else {
+            WindowMode::Windowed
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingsItem {
+    Volume(Bus),
+    Mute,
+    Theme,
+    Language,
+    WindowScale,
+    Fullscreen,
+    ShowFps,
+    Ghosting,
+    SegmentDigits,
+    KeyBindings,
+}
+
+fn step_volume(volume: &mut f32, step: i32) {
+    *volume = (*volume + step as f32 * VOLUME_STEP).clamp(0.0, 1.0);
+}
+
+fn cycle<T: PartialEq + Copy>(options: &[T], current: T, step: i32) -> T {
+    let index = options
+        .iter()
+        .position(|&option| option == current)
+        .unwrap_or(0);
+    let len = options.len() as i32;
+
+    options[(index as i32 + step).rem_euclid(len) as usize]
+}
+
+impl SettingsItem {
+    pub const ALL: [Self; 13] = [
+        Self::Volume(Bus::Master),
+        Self::Volume(Bus::Effects),
+        Self::Volume(Bus::Engine),
+        Self::Volume(Bus::Music),
+        Self::Mute,
+        Self::Theme,
+        Self::Language,
+        Self::WindowScale,
+        Self::Fullscreen,
+        Self::ShowFps,
+        Self::Ghosting,
+        Self::SegmentDigits,
+        Self::KeyBindings,
+    ];
+
+    /// Key of the row's label in the string tables.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Self::Volume(Bus::Master) => "settings.volume",
+            Self::Volume(Bus::Effects) => "settings.effects",
+            Self::Volume(Bus::Engine) => "settings.engine",
+            Self::Volume(Bus::Music) => "settings.music",
+            Self::Mute => "settings.mute",
+            Self::Theme => "settings.theme",
+            Self::Language => "settings.language",
+            Self::WindowScale => "settings.scale",
+            Self::Fullscreen => "settings.fullscreen",
+            Self::ShowFps => "settings.show_fps",
+            Self::Ghosting => "settings.ghosting",
+            Self::SegmentDigits => "settings.digits",
+            Self::KeyBindings => "settings.keys",
+        }
+    }
+
+    pub fn value(&self, settings: &Settings, strings: &Strings) -> String {
+        let on_off = |on| {
+            strings
+                .get(if on { "settings.on" } else { "settings.off" })
+                .to_string()
+        };
+        let percent = |volume: f32| format!("{}%", (volume * 100.0).round());
+
+        match self {
+            Self::Volume(bus) => percent(settings.mixer.volume(*bus)),
+            Self::Mute => on_off(settings.mixer.muted),
+            Self::Theme => strings.get(settings.theme.key()).to_string(),
+            Self::Language => settings.language.label().to_string(),
+            Self::WindowScale => match settings.window_scale {
+                Some(scale) => format!("{}X", scale),
+                None => strings.get("settings.auto").to_string(),
+            },
+            Self::Fullscreen => on_off(settings.fullscreen),
+            Self::ShowFps => on_off(settings.show_fps),
+            Self::Ghosting => on_off(settings.ghosting),
+            Self::SegmentDigits => strings
+                .get(if settings.segment_digits {
+                    "settings.tiles"
+                } else {
+                    "settings.font"
+                })
+                .to_string(),
+            Self::KeyBindings => "...".to_string(),
+        }
+    }
+
+    /// Like `adjust`, except that a row a `--scale` or `--mute` session is
+    /// hiding first goes back to the saved setting, which the override was
+    /// covering, and leaves it unchanged.
+    pub fn adjust_in_session(
+        &self,
+        settings: &mut Settings,
+        step: i32,
+        scale: &mut ScaleOverride,
+        mute: &mut MuteOverride,
+    ) {
+        match self {
+            Self::WindowScale if scale.0.is_some() => scale.0 = None,
+            Self::Mute if mute.0 => mute.0 = false,
+            _ => self.adjust(settings, step),
+        }
+    }
+
+    /// Moves the setting `step` notches; toggles ignore the direction.
+    pub fn adjust(&self, settings: &mut Settings, step: i32) {
+        match self {
+            Self::Volume(bus) => step_volume(settings.mixer.volume_mut(*bus), step),
+            Self::Mute => settings.mixer.muted = !settings.mixer.muted,
+            Self::Theme => settings.theme = cycle(&ThemePreset::ALL, settings.theme, step),
+            Self::Language => settings.language = cycle(&Language::ALL, settings.language, step),
+            Self::WindowScale => {
+                settings.window_scale = cycle(&WINDOW_SCALES, settings.window_scale, step)
+            }
+            Self::Fullscreen => settings.fullscreen = !settings.fullscreen,
+            Self::ShowFps => settings.show_fps = !settings.show_fps,
+            Self::Ghosting => settings.ghosting = !settings.ghosting,
+            Self::SegmentDigits => settings.segment_digits = !settings.segment_digits,
+            Self::KeyBindings => {}
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct SettingsRow(pub usize);
+
+#[derive(Resource)]
+pub struct SettingsScreen {
+    pub root: Entity,
+    pub selected: usize,
+}
+
+#[derive(Component)]
+pub struct FpsText;
+
+pub fn setup_settings(mut commands: Commands, asset_server: Res<AssetServer>, theme: Res<Theme>) {
+    let text_style = TextStyle {
+        font: asset_server.load("fonts/Calculator.ttf"),
+        font_size: 20.0,
+        ..default()
+    };
+
+    let root = commands
+        .spawn(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(Themed::Background)
+        .with_children(|parent| {
+            for (index, _) in SettingsItem::ALL.iter().enumerate() {
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                size: Size::new(Val::Px(280.0), Val::Px(28.0)),
+                                margin: UiRect::all(Val::Px(2.0)),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            background_color: theme.button.into(),
+                            ..default()
+                        },
+                        SettingsRow(index),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            TextBundle::from_section("", text_style.clone()),
+                            Themed::ButtonText,
+                        ));
+                    });
+            }
+        })
+        .id();
+
+    commands.insert_resource(SettingsScreen { root, selected: 0 });
+}
+
+pub fn settings_menu(
+    mut state: ResMut<State<GameState>>,
+    mut screen: ResMut<SettingsScreen>,
+    mut settings: ResMut<Settings>,
+    mut scale: ResMut<ScaleOverride>,
+    mut mute: ResMut<MuteOverride>,
+    bindings: Res<KeyBindings>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    interaction_query: Query<(&Interaction, &SettingsRow), Changed<Interaction>>,
+) {
+    let mut step = 0;
+
+    for (interaction, row) in &interaction_query {
+        if *interaction == Interaction::Clicked {
+            screen.selected = row.0;
+            step = 1;
+        }
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Up) {
+        screen.selected = screen.selected.saturating_sub(1);
+    }
+    if keyboard_input.just_pressed(KeyCode::Down) {
+        screen.selected = (screen.selected + 1).min(SettingsItem::ALL.len() - 1);
+    }
+    if keyboard_input.just_pressed(KeyCode::Left) {
+        step = -1;
+    }
+    if keyboard_input.just_pressed(KeyCode::Right)
+        || bindings.clear_just_pressed(&mut keyboard_input, InputAction::Confirm)
+    {
+        step = 1;
+    }
+
+    let item = SettingsItem::ALL[screen.selected];
+    if step != 0 {
+        if item == SettingsItem::KeyBindings {
+            state.set(GameState::Controls).unwrap();
+            return;
+        }
+
+        item.adjust_in_session(&mut settings, step, &mut scale, &mut mute);
+    }
+
+    if keyboard_input.clear_just_pressed(KeyCode::Escape) {
+        state.set(GameState::Menu).unwrap();
+    }
+}
+
+pub fn update_settings(
+    screen: Res<SettingsScreen>,
+    settings: Res<Settings>,
+    scale: Res<ScaleOverride>,
+    mute: Res<MuteOverride>,
+    theme: Res<Theme>,
+    strings: Res<Strings>,
+    mut rows_query: Query<(&SettingsRow, &Children, &mut BackgroundColor)>,
+    mut text_query: Query<&mut Text>,
+) {
+    // What is in effect, session overrides included.
+    let mut shown = settings.clone();
+    shown.window_scale = settings.window_scale_with(*scale);
+    shown.mixer.muted = settings.mixer.is_muted(*mute);
+
+    for (row, children, mut color) in &mut rows_query {
+        let item = SettingsItem::ALL[row.0];
+
+        *color = if row.0 == screen.selected {
+            theme.button_hovered.into()
+        } else {
+            theme.button.into()
+        };
+
+        if let Ok(mut text) = text_query.get_mut(children[0]) {
+            text.sections[0].value = format!(
+                "{}  {}",
+                strings.get(item.key()),
+                item.value(&shown, &strings)
+            );
+        }
+    }
+}
+
+pub fn cleanup_settings(
+    mut commands: Commands,
+    screen: Res<SettingsScreen>,
+    settings: Res<Settings>,
+) {
+    commands.entity(screen.root).despawn_recursive();
+    settings.save_user();
+}
+
+/// Pushes changed settings out to the window and the theme.
+pub fn apply_settings(
+    settings: Res<Settings>,
+    scale: Res<ScaleOverride>,
+    mut windows: ResMut<Windows>,
+    mut theme: ResMut<Theme>,
+    mut clear_color: ResMut<ClearColor>,
+    mut strings: ResMut<Strings>,
+) {
+    if !settings.is_changed() && !scale.is_changed() {
+        return;
+    }
+
+    if let Some(window) = windows.get_primary_mut() {
+        if window.mode() != settings.window_mode() {
+            window.set_mode(settings.window_mode());
+        }
+        let window_scale = settings.window_scale_with(*scale);
+        if window.scale_factor_override() != window_scale {
+            window.set_scale_factor_override(window_scale);
+        }
+    }
+
+    let preset = Theme::preset(settings.theme);
+    if *theme != preset {
+        *theme = preset;
+        clear_color.0 = theme.background;
+    }
+
+    if strings.language != settings.language {
+        *strings = Strings::new(settings.language);
+    }
+}
+
+pub fn setup_fps(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load("fonts/Calculator.ttf"),
+                font_size: 20.0,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(4.0),
+                left: Val::Px(4.0),
+                ..default()
+            },
+            ..default()
+        }),
+        FpsText,
+        Themed::Text,
+    ));
+}
+
+pub fn update_fps(
+    settings: Res<Settings>,
+    diagnostics: Res<Diagnostics>,
+    mut fps_query: Query<(&mut Text, &mut Visibility), With<FpsText>>,
+) {
+    let fps = diagnostics
+        .get(FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed());
+
+    for (mut text, mut visibility) in &mut fps_query {
+        visibility.is_visible = settings.show_fps;
+        if let Some(fps) = fps {
+            text.sections[0].value = format!("{:.0} FPS", fps);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn missing_fields_keep_their_defaults() {
+        let path = std::env::temp_dir().join("bevy_retro_racing_settings_test.ron");
+        fs::write(&path, "(show_fps: true, mixer: (effects: 0.5))").unwrap();
+
+        let settings = Settings::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            settings,
+            Settings {
+                show_fps: true,
+                mixer: Mixer {
+                    effects: 0.5,
+                    ..default()
+                },
+                ..default()
+            }
+        );
+        assert_eq!(
+            Settings::load(&path),
+            Settings::default(),
+            "a missing file gives the defaults"
+        );
+    }
+
+    #[test]
+    fn out_of_range_values_are_brought_back() {
+        let path = std::env::temp_dir().join("bevy_retro_racing_settings_range_test.ron");
+        fs::write(
+            &path,
+            "(window_scale: Some(-2.0), mixer: (master: 4.0, music: -1.0), repeat: (interval: 0))",
+        )
+        .unwrap();
+
+        let settings = Settings::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(settings.mixer.master, 1.0);
+        assert_eq!(settings.mixer.music, 0.0);
+        assert_eq!(settings.window_scale, None);
+        assert_eq!(settings.repeat.interval, *REPEAT_INTERVAL_RANGE.start());
+    }
+
+    #[test]
+    fn a_launch_scale_is_never_saved() {
+        use clap::Parser;
+
+        let path = std::env::temp_dir().join("bevy_retro_racing_settings_scale_test.ron");
+        Settings::default().save(&path).unwrap();
+
+        let options = LaunchOptions::parse_from(["bevy_retro_racing", "--scale", "2"]);
+        let scale = ScaleOverride(options.scale);
+        let settings = Settings::load(&path);
+        assert_eq!(settings.window_scale_with(scale), Some(2.0));
+
+        // Leaving the settings screen saves what is in the resource.
+        settings.save(&path).unwrap();
+        let saved = Settings::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(saved.window_scale, None);
+        assert_eq!(saved.window_scale_with(ScaleOverride(None)), None);
+    }
+
+    #[test]
+    fn a_session_override_is_lifted_before_the_setting_changes() {
+        let mut settings = Settings::default();
+        let mut scale = ScaleOverride(Some(2.0));
+        let mut mute = MuteOverride(true);
+
+        SettingsItem::Mute.adjust_in_session(&mut settings, 1, &mut scale, &mut mute);
+        assert_eq!(mute, MuteOverride(false));
+        assert!(!settings.mixer.muted);
+        assert!(!settings.mixer.is_muted(mute));
+        SettingsItem::Mute.adjust_in_session(&mut settings, 1, &mut scale, &mut mute);
+        assert!(settings.mixer.muted);
+
+        SettingsItem::WindowScale.adjust_in_session(&mut settings, 1, &mut scale, &mut mute);
+        assert_eq!(scale, ScaleOverride(None));
+        assert_eq!(settings.window_scale, None);
+        SettingsItem::WindowScale.adjust_in_session(&mut settings, 1, &mut scale, &mut mute);
+        assert_eq!(settings.window_scale, Some(1.0));
+    }
+
+    #[test]
+    fn adjusting_clamps_and_cycles() {
+        let mut settings = Settings::default();
+
+        let master = SettingsItem::Volume(Bus::Master);
+        master.adjust(&mut settings, 1);
+        assert_eq!(settings.mixer.master, 1.0);
+        master.adjust(&mut settings, -1);
+        assert!((settings.mixer.master - 0.9).abs() < 1e-6);
+
+        SettingsItem::WindowScale.adjust(&mut settings, -1);
+        assert_eq!(settings.window_scale, Some(3.0));
+        SettingsItem::WindowScale.adjust(&mut settings, 1);
+        assert_eq!(settings.window_scale, None);
+
+        SettingsItem::Language.adjust(&mut settings, -1);
+        assert_eq!(settings.language, Language::Spanish);
+        assert_eq!(
+            SettingsItem::Theme.value(&settings, &Strings::new(settings.language)),
+            "CLASICO"
+        );
+    }
+}
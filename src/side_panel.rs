@@ -0,0 +1,225 @@
+use crate::prelude::*;
+use std::time::Duration;
+
+/// Cells along each side of the "next" preview.
+pub const PREVIEW_SIZE: usize = 4;
+pub const PREVIEW_TILE: f32 = 10.0;
+pub const ICON_SIZE: usize = 5;
+pub const ICON_TILE: f32 = 6.0;
+/// How often the preview icon shakes or steps down its animation.
+pub const PREVIEW_BLINK: Duration = Duration::from_millis(250);
+
+/// The coffee cup the handheld lights while paused.
+pub const PAUSE_ICON: [&str; ICON_SIZE] = ["_O_O_", "_____", "OOOO_", "OOOOO", "OOOO_"];
+/// The speaker lit while sound is on.
+pub const SOUND_ICON: [&str; ICON_SIZE] = ["__O__", "_OO_O", "OOO_O", "_OO_O", "__O__"];
+
+/// What a side panel display shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PanelItem {
+    Next,
+    Pause,
+    Sound,
+}
+
+/// A small tile display in the side panel, redrawn when its cells change.
+#[derive(Component)]
+pub struct PanelTiles {
+    pub item: PanelItem,
+    image: Handle<Image>,
+    drawn: Vec<Option<bool>>,
+}
+
+/// The "next" preview: the coming car's icon sits left or right of centre
+/// as it comes down left or right of the player, and shakes between the two
+/// when it is headed for the player's lane. Without a run it drives down the
+/// grid on a loop.
+pub fn preview_grid(race: Option<&Race>, elapsed: Duration) -> TileGrid {
+    let mut grid = TileGrid::new(PREVIEW_SIZE, PREVIEW_SIZE);
+    let step = (elapsed.as_millis() / PREVIEW_BLINK.as_millis()) as i32;
+
+    let (left, row) = match race.and_then(|race| Some((race, race.next_rival()?))) {
+        Some((race, rival)) => {
+            let left = match rival.lane.cmp(&race.lane) {
+                std::cmp::Ordering::Less => 0,
+                std::cmp::Ordering::Greater => 1,
+                std::cmp::Ordering::Equal => step % 2,
+            };
+            (left, 0)
+        }
+        None => {
+            let lap = (PREVIEW_SIZE + CAR.len()) as i32;
+            (0, PREVIEW_SIZE as i32 - step % lap)
+        }
+    };
+
+    let top = row + CAR.len() as i32 - 1;
+    for (y, line) in CAR.iter().enumerate() {
+        for (x, c) in line.chars().enumerate() {
+            if c == 'O' {
+                grid.light(left + x as i32, top - y as i32);
+            }
+        }
+    }
+
+    grid
+}
+
+/// Cells of a grid, top row first, all shown.
+fn grid_cells(grid: &TileGrid) -> Vec<Option<bool>> {
+    grid.rows().flatten().map(|&lit| Some(lit)).collect()
+}
+
+/// Cells of an icon, top row first: its shape lit when `on`, unlit
+/// otherwise, and nothing around it.
+pub fn icon_cells(icon: &[&str; ICON_SIZE], on: bool) -> Vec<Option<bool>> {
+    icon.iter()
+        .flat_map(|line| line.chars())
+        .map(|c| (c == 'O').then_some(on))
+        .collect()
+}
+
+/// Spawns a panel display of `size` x `size` cells, placed at `position`
+/// from its parent's edges.
+pub fn spawn_panel_tiles(
+    commands: &mut Commands,
+    images: &mut Assets<Image>,
+    item: PanelItem,
+    size: usize,
+    tile: f32,
+    position: UiRect,
+) -> Entity {
+    let image = images.add(tile_image(size, size));
+
+    commands
+        .spawn((
+            ImageBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position,
+                    size: Size::new(Val::Px(size as f32 * tile), Val::Px(size as f32 * tile)),
+                    ..default()
+                },
+                image: image.clone().into(),
+                ..default()
+            },
+            PanelTiles {
+                item,
+                image,
+                drawn: Vec::new(),
+            },
+        ))
+        .id()
+}
+
+pub fn update_side_panel(
+    state: Res<State<GameState>>,
+    settings: Res<Settings>,
+    mute: Res<MuteOverride>,
+    theme: Res<Theme>,
+    race: Res<Race>,
+    time: Res<Time>,
+    mut images: ResMut<Assets<Image>>,
+    mut panels: Query<&mut PanelTiles>,
+) {
+    let racing = matches!(
+        state.current(),
+        GameState::Playing | GameState::Paused | GameState::Demo | GameState::Replay
+    );
+
+    for mut panel in &mut panels {
+        let (size, cells) = match panel.item {
+            PanelItem::Next => {
+                let race = (racing && !race.is_over()).then_some(&*race);
+                (
+                    PREVIEW_SIZE,
+                    grid_cells(&preview_grid(race, time.elapsed())),
+                )
+            }
+            PanelItem::Pause => (
+                ICON_SIZE,
+                icon_cells(&PAUSE_ICON, *state.current() == GameState::Paused),
+            ),
+            PanelItem::Sound => (
+                ICON_SIZE,
+                icon_cells(&SOUND_ICON, !settings.mixer.is_muted(*mute)),
+            ),
+        };
+        if cells == panel.drawn && !theme.is_changed() {
+            continue;
+        }
+
+        if let Some(image) = images.get_mut(&panel.image) {
+            rasterize_cells(&mut image.data, size, size, &theme, |x, y| {
+                cells[(size - 1 - y) * size + x].map(|lit| lit as u8 as f32)
+            });
+        }
+        panel.drawn = cells;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drawn(grid: &TileGrid) -> Vec<String> {
+        grid.rows()
+            .map(|row| row.iter().map(|&lit| if lit { 'O' } else { '_' }).collect())
+            .collect()
+    }
+
+    fn race_with_next_lane(lane: usize) -> Race {
+        let mut race = Race::default();
+        race.lane = 1;
+        for (i, rival) in race.rivals.iter_mut().enumerate() {
+            rival.row = SCREEN_HEIGHT as i32 + i as i32 * CAR_SPACING as i32;
+            rival.lane = 0;
+        }
+        race.rivals[0].lane = lane;
+        race
+    }
+
+    #[test]
+    fn preview_shows_the_side_of_the_next_car() {
+        let race = race_with_next_lane(0);
+        assert_eq!(race.next_rival().unwrap().lane, 0);
+        assert_eq!(
+            drawn(&preview_grid(Some(&race), Duration::ZERO)),
+            ["_O__", "OOO_", "_O__", "O_O_"]
+        );
+
+        let race = race_with_next_lane(2);
+        assert_eq!(
+            drawn(&preview_grid(Some(&race), Duration::ZERO)),
+            ["__O_", "_OOO", "__O_", "_O_O"]
+        );
+
+        // Headed for the player: the icon shakes from side to side.
+        let race = race_with_next_lane(1);
+        let still = preview_grid(Some(&race), Duration::ZERO);
+        let shaken = preview_grid(Some(&race), PREVIEW_BLINK);
+        assert_ne!(still, shaken);
+        assert_eq!(preview_grid(Some(&race), PREVIEW_BLINK * 2), still);
+    }
+
+    #[test]
+    fn preview_drives_down_without_a_run() {
+        let entering = drawn(&preview_grid(None, PREVIEW_BLINK));
+        assert_eq!(entering, ["O_O_", "____", "____", "____"]);
+
+        let lap = PREVIEW_BLINK * (PREVIEW_SIZE + CAR.len()) as u32;
+        assert_eq!(preview_grid(None, Duration::ZERO), preview_grid(None, lap));
+    }
+
+    #[test]
+    fn icons_keep_their_shape_lit_or_not() {
+        let on = icon_cells(&SOUND_ICON, true);
+        let off = icon_cells(&SOUND_ICON, false);
+
+        assert_eq!(on.len(), ICON_SIZE * ICON_SIZE);
+        assert_eq!(on[2], Some(true));
+        assert_eq!(off[2], Some(false));
+        assert_eq!(on[0], None);
+        assert_eq!(off[0], None);
+    }
+}
@@ -1,111 +1,67 @@
 use crate::prelude::*;
+use bevy::window::WindowMode;
 
-const CAR: [&str; 4] = ["_O_", "OOO", "_O_", "O_O"];
-
-const LEFT_WALL_X: f32 = SCREEN_X + HALF_TILE;
-const RIGHT_WALL_X: f32 = SCREEN_X + SCREEN_WIDTH as f32 * TILE_SIZE - HALF_TILE;
-
-fn anchor_sprite(x: f32, y: f32) -> SpriteBundle {
-    let pos = Vec2::new(x, y);
-    SpriteBundle {
-        sprite: Sprite {
-            color: Color::rgba(0.0, 0.0, 0.0, 0.0),
-            ..default()
-        },
-        transform: Transform {
-            translation: pos.extend(0.0),
-            ..default()
-        },
-        ..default()
-    }
+/// Where a car or wall sits; the tile layer draws it.
+fn anchor(x: f32, y: f32) -> SpatialBundle {
+    SpatialBundle::from_transform(Transform::from_xyz(x, y, 0.0))
 }
 
-fn draw_car(parent: &mut ChildBuilder) {
-    let sprite = Sprite {
-        custom_size: Some(Vec2::splat(TILE_SIZE)),
-        color: TILE_COLOR,
-        ..default()
-    };
-
-    for (y, line) in CAR.iter().enumerate() {
-        for (x, c) in line.chars().enumerate() {
-            let pos = Vec2::new(
-                x as f32 * TILE_SIZE - TILE_SIZE,
-                y as f32 * -TILE_SIZE + TILE_SIZE + HALF_TILE,
-            );
+/// Rebuilds the playfield and resizes the window after the lane count
+/// changes from the menu.
+pub fn resize_playfield(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    race: Res<Race>,
+    mut windows: ResMut<Windows>,
+    layout: Res<ScreenLayout>,
+    stale_query: Query<Entity, Or<(With<TileLayer>, With<Wall>)>>,
+    mut lanes: Local<Option<usize>>,
+) {
+    let current = race.config.lanes;
+    if lanes.replace(current).unwrap_or(current) == current {
+        return;
+    }
 
-            if c == 'O' {
-                parent.spawn(SpriteBundle {
-                    sprite: sprite.clone(),
-                    transform: Transform {
-                        scale: TileScreen::tile_scale(),
-                        translation: Vec2::extend(pos, 0.0),
-                        ..default()
-                    },
-                    ..default()
-                });
-            }
-        }
+    for entity in &stale_query {
+        commands.entity(entity).despawn_recursive();
+    }
+    spawn_tile_layer(&mut commands, &mut images, current);
+    spawn_wall_rows(&mut commands, &race);
+
+    // Keep the current magnification; `fit_screen` picks up the new width.
+    let window = windows.primary_mut();
+    if window.mode() == WindowMode::Windowed {
+        let size = ScreenLayout::screen_size(current) * layout.logical_scale();
+        window.set_resolution(size.x, size.y);
+        window.center_window(MonitorSelection::Current);
     }
 }
 
-fn draw_walls(parent: &mut ChildBuilder) {
-    let sprite = Sprite {
-        custom_size: Some(Vec2::splat(TILE_SIZE)),
-        color: TILE_COLOR,
-        ..default()
-    };
-
-    for y in 0..3 {
-        let pos_y = y as f32 * TILE_SIZE - HALF_TILE;
-
-        parent.spawn(SpriteBundle {
-            sprite: sprite.clone(),
-            transform: Transform {
-                scale: TileScreen::tile_scale(),
-                translation: Vec3::new(LEFT_WALL_X, pos_y, 0.0),
-                ..default()
-            },
-            ..default()
-        });
-
-        parent.spawn(SpriteBundle {
-            sprite: sprite.clone(),
-            transform: Transform {
-                scale: TileScreen::tile_scale(),
-                translation: Vec3::new(RIGHT_WALL_X, pos_y, 0.0),
-                ..default()
-            },
-            ..default()
-        });
+pub fn spawn_walls(mut commands: Commands, race: Res<Race>, walls_query: Query<(), With<Wall>>) {
+    // Menu and run share the walls, only the first one spawns them.
+    if !walls_query.is_empty() {
+        return;
     }
-}
 
-pub fn spawn_walls(mut commands: Commands) {
-    for y in 0..6 {
-        let pos_y = SCREEN_Y as f32 + TILE_SIZE;
-        let y_distance = y as f32 * TILE_SIZE * WALL_SPACING;
+    spawn_wall_rows(&mut commands, &race);
+}
 
-        commands
-            .spawn((Wall, MoveY))
-            .with_children(draw_walls)
-            .insert(anchor_sprite(0.0, pos_y + y_distance));
+fn spawn_wall_rows(commands: &mut Commands, race: &Race) {
+    for (slot, &row) in race.walls.iter().enumerate() {
+        commands.spawn((Wall(slot), anchor(0.0, TileScreen::wall_coord(row))));
     }
 }
 
-pub fn spawn_enemies(mut commands: Commands /* asset_server: Res<AssetServer> */) {
-    let mut rng = thread_rng();
-
-    for y in 0..4 {
-        let column = rng.gen_range(0..3);
-        let pos_x = TileScreen::column_to_coord(column);
-        let pos_y = SCREEN_Y + SCREEN_HEIGHT as f32 * TILE_SIZE + TILE_SIZE * CAR_SPACING;
-        let y_distance = y as f32 * TILE_SIZE * CAR_SPACING;
+pub fn spawn_enemies(mut commands: Commands, race: Res<Race>) {
+    for (slot, rival) in race.rivals.iter().enumerate() {
+        let pos_x = TileScreen::column_to_coord(rival.lane);
+        let pos_y = TileScreen::car_coord(rival.row);
 
-        commands
-            .spawn((Car { column }, MoveY, Enemy))
-            .with_children(draw_car)
-            .insert(anchor_sprite(pos_x, pos_y + y_distance));
+        commands.spawn((
+            Car { column: rival.lane },
+            Enemy(slot),
+            anchor(pos_x, pos_y),
+        ));
 
         // [Debug] Show car numbers
         //
@@ -116,23 +72,19 @@ pub fn spawn_enemies(mut commands: Commands /* asset_server: Res<AssetServer> */
         // };
         // commands
         //     .spawn_bundle(Text2dBundle {
-        //         text: Text::from_section(format!("{}", y), text_style.clone()),
+        //         text: Text::from_section(format!("{}", slot), text_style.clone()),
         //         transform: Transform {
-        //             translation: Vec3::new(pos_x, pos_y + y_distance, 1.0),
+        //             translation: Vec3::new(pos_x, pos_y, 1.0),
         //             ..default()
         //         },
         //         ..default()
-        //     })
-        //     .insert(MoveY);
+        //     });
     }
 }
 
-pub fn spawn_player(mut commands: Commands) {
-    let column = 1;
+pub fn spawn_player(mut commands: Commands, race: Res<Race>) {
+    let column = race.lane;
     let pos_x = TileScreen::column_to_coord(column);
 
-    commands
-        .spawn((Car { column }, Player))
-        .with_children(draw_car)
-        .insert(anchor_sprite(pos_x, PLAYER_Y));
+    commands.spawn((Car { column }, Player, anchor(pos_x, PLAYER_Y)));
 }
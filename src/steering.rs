@@ -0,0 +1,292 @@
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::ops::RangeInclusive;
+use std::time::Duration;
+
+pub const REPEAT_DELAY: Duration = Duration::from_millis(180);
+pub const REPEAT_INTERVAL: Duration = Duration::from_millis(60);
+/// Delays the settings file may ask for.
+pub const REPEAT_DELAY_RANGE: RangeInclusive<Duration> =
+    Duration::from_millis(50)..=Duration::from_millis(1000);
+/// Intervals the settings file may ask for; never zero, which would repeat
+/// without end.
+pub const REPEAT_INTERVAL_RANGE: RangeInclusive<Duration> =
+    Duration::from_millis(20)..=Duration::from_millis(500);
+/// Steers buffered beyond this are dropped rather than played late.
+pub const QUEUE_LIMIT: usize = 4;
+
+/// Delayed auto-repeat: a held direction steers once when pressed, again
+/// after `delay`, then every `interval` until released. Saved in the
+/// settings as milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutoRepeat {
+    #[serde(with = "millis")]
+    pub delay: Duration,
+    #[serde(with = "millis")]
+    pub interval: Duration,
+}
+
+impl AutoRepeat {
+    /// The timings brought within `REPEAT_DELAY_RANGE` and
+    /// `REPEAT_INTERVAL_RANGE`.
+    pub fn clamped(self) -> Self {
+        Self {
+            delay: self
+                .delay
+                .clamp(*REPEAT_DELAY_RANGE.start(), *REPEAT_DELAY_RANGE.end()),
+            interval: self
+                .interval
+                .clamp(*REPEAT_INTERVAL_RANGE.start(), *REPEAT_INTERVAL_RANGE.end()),
+        }
+    }
+}
+
+mod millis {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(duration.as_millis() as u64)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_millis)
+    }
+}
+
+impl Default for AutoRepeat {
+    fn default() -> Self {
+        Self {
+            delay: REPEAT_DELAY,
+            interval: REPEAT_INTERVAL,
+        }
+    }
+}
+
+/// Lane changes waiting to be played, one per frame, so presses that land
+/// in the same frame are not merged.
+#[derive(Resource, Debug, Default)]
+pub struct SteerQueue {
+    /// Only set through `new`, so the interval is never zero.
+    repeat: AutoRepeat,
+    queue: VecDeque<Steer>,
+    holding: Vec<Steer>,
+    held: Steer,
+    held_for: Duration,
+    repeats: u32,
+}
+
+impl SteerQueue {
+    /// A queue repeating with `repeat`, clamped to the allowed timings.
+    pub fn new(repeat: AutoRepeat) -> Self {
+        Self {
+            repeat: repeat.clamped(),
+            ..default()
+        }
+    }
+
+    pub fn repeat(&self) -> AutoRepeat {
+        self.repeat
+    }
+
+    /// A fresh press of a direction.
+    pub fn press(&mut self, steer: Steer) {
+        if self.queue.len() < QUEUE_LIMIT {
+            self.queue.push_back(steer);
+        }
+
+        self.held = steer;
+        self.held_for = Duration::ZERO;
+        self.repeats = 0;
+    }
+
+    /// A direction still held down this frame.
+    pub fn hold(&mut self, steer: Steer) {
+        self.holding.push(steer);
+    }
+
+    /// Advances the auto-repeat by one frame and returns the steer to play.
+    pub fn tick(&mut self, delta: Duration) -> Option<Steer> {
+        let left = self.holding.contains(&Steer::Left);
+        let right = self.holding.contains(&Steer::Right);
+        self.holding.clear();
+
+        let held = match (left, right) {
+            (true, false) => Steer::Left,
+            (false, true) => Steer::Right,
+            _ => Steer::Straight,
+        };
+
+        if held != self.held {
+            self.held = held;
+            self.held_for = Duration::ZERO;
+            self.repeats = 0;
+        } else if held != Steer::Straight {
+            self.held_for += delta;
+            while self.held_for >= self.repeat.delay + self.repeat.interval * self.repeats {
+                if self.queue.len() < QUEUE_LIMIT {
+                    self.queue.push_back(held);
+                }
+                self.repeats += 1;
+            }
+        }
+
+        self.queue.pop_front()
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::new(self.repeat);
+    }
+}
+
+pub fn reset_steering(mut steering: ResMut<SteerQueue>) {
+    steering.clear();
+}
+
+pub fn steer_player(
+    mut steering: ResMut<SteerQueue>,
+    mut race: ResMut<Race>,
+    timer: Res<Time>,
+    mut sfx: EventWriter<Sfx>,
+) {
+    if let Some(steer) = steering.tick(timer.delta()) {
+        if race.steer(steer) {
+            sfx.send(Sfx::LaneChange);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::input::keyboard::KeyboardInput;
+    use bevy::input::{ButtonState, InputPlugin};
+
+    const FRAME: Duration = Duration::from_millis(20);
+
+    fn frames(steering: &mut SteerQueue, held: Steer, count: usize) -> Vec<Steer> {
+        (0..count)
+            .filter_map(|_| {
+                steering.hold(held);
+                steering.tick(FRAME)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn double_tap_in_one_frame_steers_twice() {
+        let mut steering = SteerQueue::default();
+
+        steering.press(Steer::Left);
+        steering.press(Steer::Left);
+
+        assert_eq!(
+            frames(&mut steering, Steer::Straight, 3),
+            [Steer::Left, Steer::Left]
+        );
+    }
+
+    #[test]
+    fn holding_repeats_after_the_delay() {
+        let mut steering = SteerQueue::new(AutoRepeat {
+            delay: Duration::from_millis(100),
+            interval: Duration::from_millis(40),
+        });
+
+        steering.press(Steer::Right);
+        // Frame 1 plays the press, frame 5 reaches the delay and every
+        // second frame after that repeats.
+        let steers: Vec<_> = (1..=10)
+            .filter_map(|frame| {
+                steering.hold(Steer::Right);
+                steering.tick(FRAME).map(|steer| (frame, steer))
+            })
+            .collect();
+
+        assert_eq!(
+            steers,
+            [
+                (1, Steer::Right),
+                (5, Steer::Right),
+                (7, Steer::Right),
+                (9, Steer::Right)
+            ]
+        );
+    }
+
+    #[test]
+    fn timings_are_kept_in_bounds() {
+        let mut steering = SteerQueue::new(AutoRepeat {
+            delay: Duration::ZERO,
+            interval: Duration::ZERO,
+        });
+        assert_eq!(steering.repeat().interval, *REPEAT_INTERVAL_RANGE.start());
+        assert_eq!(steering.repeat().delay, *REPEAT_DELAY_RANGE.start());
+
+        // The press, then every frame from the third on.
+        steering.press(Steer::Left);
+        assert_eq!(frames(&mut steering, Steer::Left, 10).len(), 9);
+
+        let saved: AutoRepeat = ron::from_str("(delay: 250)").unwrap();
+        assert_eq!(saved.delay, Duration::from_millis(250));
+        assert_eq!(saved.interval, REPEAT_INTERVAL);
+    }
+
+    #[test]
+    fn releasing_stops_the_repeat() {
+        let mut steering = SteerQueue::default();
+
+        steering.press(Steer::Left);
+        frames(&mut steering, Steer::Left, 2);
+
+        assert!(frames(&mut steering, Steer::Straight, 50).is_empty());
+    }
+
+    #[test]
+    fn holding_both_directions_does_not_repeat() {
+        let mut steering = SteerQueue::default();
+
+        for _ in 0..50 {
+            steering.hold(Steer::Left);
+            steering.hold(Steer::Right);
+            assert_eq!(steering.tick(FRAME), None);
+        }
+    }
+
+    #[test]
+    fn key_taps_between_frames_are_not_merged() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(InputPlugin)
+            .add_state(GameState::Playing)
+            .insert_resource(Race::new(RaceConfig {
+                lanes: 3,
+                level: 0,
+                seed: 1,
+            }))
+            .init_resource::<KeyBindings>()
+            .init_resource::<SteerQueue>()
+            .add_event::<Sfx>()
+            .add_system(move_player)
+            .add_system(steer_player.after(move_player));
+
+        let key = |state| KeyboardInput {
+            scan_code: 0,
+            key_code: Some(KeyCode::Right),
+            state,
+        };
+        app.world.send_event(key(ButtonState::Pressed));
+        app.world.send_event(key(ButtonState::Released));
+        app.world.send_event(key(ButtonState::Pressed));
+        app.world.send_event(key(ButtonState::Released));
+
+        app.update();
+        assert_eq!(app.world.resource::<Race>().lane, 2);
+
+        app.world.resource_mut::<Race>().lane = 0;
+        app.update();
+        assert_eq!(app.world.resource::<Race>().lane, 1);
+    }
+}
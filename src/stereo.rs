@@ -0,0 +1,243 @@
+use crate::prelude::*;
+use bevy::asset::Asset;
+use bevy::audio::{play_queued_audio_system, AudioOutput, CpalSample, Sample, Source};
+use bevy::reflect::{TypeUuid, Uuid};
+use std::f32::consts::FRAC_PI_4;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How far the outermost lanes pan; below 1 so they stay audible in both ears.
+pub const PAN_WIDTH: f32 = 0.8;
+
+/// Stereo position of a lane, from -`PAN_WIDTH` (left) to `PAN_WIDTH` (right).
+pub fn lane_pan(lanes: usize, lane: usize) -> f32 {
+    if lanes < 2 {
+        return 0.0;
+    }
+
+    (lane as f32 / (lanes - 1) as f32 * 2.0 - 1.0) * PAN_WIDTH
+}
+
+/// Left and right gains for `pan`, keeping the loudness constant.
+pub fn pan_gains(pan: f32) -> (f32, f32) {
+    let angle = (pan.clamp(-1.0, 1.0) + 1.0) * FRAC_PI_4;
+    (angle.cos(), angle.sin())
+}
+
+/// Lanes of the rivals that drew level with the player since `before`.
+pub fn passed_lanes(before: &[Rival], after: &[Rival]) -> Vec<usize> {
+    before
+        .iter()
+        .zip(after)
+        .filter(|(before, after)| before.row > 0 && after.row <= 0)
+        .map(|(_, after)| after.lane)
+        .collect()
+}
+
+/// A pan shared with a playing sound, so it can follow the player.
+#[derive(Debug, Clone, Default)]
+pub struct Pan(Arc<AtomicU32>);
+
+impl Pan {
+    pub fn new(pan: f32) -> Self {
+        Self(Arc::new(AtomicU32::new(pan.to_bits())))
+    }
+
+    pub fn get(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed))
+    }
+
+    pub fn set(&self, pan: f32) {
+        self.0.store(pan.to_bits(), Ordering::Relaxed);
+    }
+}
+
+/// A sound mixed down to mono and played in stereo at `pan`, through
+/// `Audio<Panned<S>>`.
+#[derive(Debug, Clone)]
+pub struct Panned<S> {
+    pub sound: S,
+    pub pan: Pan,
+}
+
+impl<S> Panned<S> {
+    pub fn new(sound: S, pan: Pan) -> Self {
+        Self { sound, pan }
+    }
+}
+
+impl TypeUuid for Panned<AudioSource> {
+    const TYPE_UUID: Uuid = Uuid::from_u128(0x7c2d_4e1a_93b5_4f60_8a1e_2b6d_5c3f_9e14);
+}
+
+impl TypeUuid for Panned<Beep> {
+    const TYPE_UUID: Uuid = Uuid::from_u128(0x0e8f_31c7_5a24_4b9d_b6f3_71a2_c4d8_e305);
+}
+
+pub struct PannedDecoder<D> {
+    inner: D,
+    pan: Pan,
+    right: Option<f32>,
+}
+
+impl<D> Iterator for PannedDecoder<D>
+where
+    D: Source,
+    D::Item: Sample,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if let Some(right) = self.right.take() {
+            return Some(right);
+        }
+
+        let channels = self.inner.channels().max(1);
+        let mut sum = self.inner.next()?.to_f32();
+        for _ in 1..channels {
+            sum += self.inner.next().map_or(0.0, |sample| sample.to_f32());
+        }
+
+        let sample = sum / channels as f32;
+        let (left, right) = pan_gains(self.pan.get());
+        self.right = Some(sample * right);
+        Some(sample * left)
+    }
+}
+
+impl<D> Source for PannedDecoder<D>
+where
+    D: Source,
+    D::Item: Sample,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        let channels = self.inner.channels().max(1) as usize;
+        self.inner
+            .current_frame_len()
+            .map(|len| len / channels * 2 + self.right.is_some() as usize)
+    }
+
+    fn channels(&self) -> u16 {
+        2
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+}
+
+impl<S> Decodable for Panned<S>
+where
+    S: Decodable,
+{
+    type Decoder = PannedDecoder<S::Decoder>;
+    type DecoderItem = f32;
+
+    fn decoder(&self) -> Self::Decoder {
+        PannedDecoder {
+            inner: self.sound.decoder(),
+            pan: self.pan.clone(),
+            right: None,
+        }
+    }
+}
+
+/// Registers the panned audio sources next to Bevy's own.
+pub struct StereoPlugin;
+
+impl Plugin for StereoPlugin {
+    fn build(&self, app: &mut App) {
+        add_panned_source::<AudioSource>(app);
+        add_panned_source::<Beep>(app);
+    }
+}
+
+fn add_panned_source<S>(app: &mut App)
+where
+    S: Decodable,
+    Panned<S>: Asset + Decodable,
+{
+    app.init_non_send_resource::<AudioOutput<Panned<S>>>()
+        .add_asset::<Panned<S>>()
+        .init_resource::<Audio<Panned<S>>>()
+        .add_system_to_stage(
+            CoreStage::PostUpdate,
+            play_queued_audio_system::<Panned<S>>.after(play_sfx),
+        );
+}
+
+/// Stereo position of the player's car.
+pub fn player_pan(race: &Race, player: &Query<&Car, With<Player>>) -> f32 {
+    let column = player.get_single().map_or(race.lane, |car| car.column);
+    lane_pan(race.config.lanes, column)
+}
+
+/// A rival drew level with the player in `lane`.
+pub struct PassBy {
+    pub lane: usize,
+}
+
+pub fn pass_by(race: Res<Race>, mut rivals: Local<Vec<Rival>>, mut passes: EventWriter<PassBy>) {
+    for lane in passed_lanes(&rivals, &race.rivals) {
+        passes.send(PassBy { lane });
+    }
+    *rivals = race.rivals.clone();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lanes_spread_from_left_to_right() {
+        assert_eq!(lane_pan(1, 0), 0.0);
+        assert_eq!(lane_pan(3, 0), -PAN_WIDTH);
+        assert_eq!(lane_pan(3, 1), 0.0);
+        assert_eq!(lane_pan(3, 2), PAN_WIDTH);
+
+        let (left, right) = pan_gains(0.0);
+        assert!((left - right).abs() < 1e-6);
+        assert!((left * left + right * right - 1.0).abs() < 1e-6);
+
+        let (left, right) = pan_gains(-PAN_WIDTH);
+        assert!(left > right && right > 0.0);
+    }
+
+    #[test]
+    fn panned_beeps_play_in_stereo() {
+        let beep = Beep::new(&Sfx::Countdown.notes());
+        let pan = Pan::new(-1.0);
+        let samples: Vec<f32> = Panned::new(beep.clone(), pan.clone()).decoder().collect();
+
+        assert_eq!(samples.len(), beep.samples().len() * 2);
+        assert!(samples.chunks(2).all(|frame| frame[1].abs() < 1e-6));
+
+        // The pan is read while playing, so the engine can follow the player.
+        let mut decoder = Panned::new(beep, pan.clone()).decoder();
+        decoder.next();
+        pan.set(1.0);
+        decoder.next();
+        assert!(decoder.next().unwrap().abs() < 1e-6);
+    }
+
+    #[test]
+    fn only_rivals_drawing_level_pass_by() {
+        let before = [
+            Rival { lane: 0, row: 1 },
+            Rival { lane: 1, row: 5 },
+            Rival { lane: 2, row: 0 },
+        ];
+        let after = [
+            Rival { lane: 0, row: 0 },
+            Rival { lane: 1, row: 4 },
+            Rival { lane: 2, row: -1 },
+        ];
+
+        assert_eq!(passed_lanes(&before, &after), [0]);
+    }
+}
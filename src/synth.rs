@@ -0,0 +1,341 @@
+use crate::prelude::*;
+use bevy::audio::Source;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub const SAMPLE_RATE: u32 = 22_050;
+/// Peak level of every waveform, leaving headroom for overlapping beeps.
+pub const AMPLITUDE: f32 = 0.25;
+pub const COUNTDOWN_BEEPS: u32 = 3;
+pub const COUNTDOWN_INTERVAL: Duration = Duration::from_millis(400);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Wave {
+    Square,
+    /// Pseudo-random noise from a 15-bit LFSR clocked at the note frequency,
+    /// like the handheld sound chips.
+    Noise,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Note {
+    pub wave: Wave,
+    /// In Hz; zero is a rest.
+    pub frequency: f32,
+    pub duration: Duration,
+}
+
+impl Note {
+    pub const fn square(frequency: f32, millis: u64) -> Self {
+        Self {
+            wave: Wave::Square,
+            frequency,
+            duration: Duration::from_millis(millis),
+        }
+    }
+
+    pub const fn noise(frequency: f32, millis: u64) -> Self {
+        Self {
+            wave: Wave::Noise,
+            frequency,
+            duration: Duration::from_millis(millis),
+        }
+    }
+
+    pub const fn rest(millis: u64) -> Self {
+        Self::square(0.0, millis)
+    }
+}
+
+/// Renders notes one after another as mono samples at `SAMPLE_RATE`.
+pub fn render(notes: &[Note]) -> Vec<f32> {
+    let mut samples = Vec::new();
+
+    for note in notes {
+        let count = (note.duration.as_secs_f64() * SAMPLE_RATE as f64).round() as usize;
+        let step = note.frequency / SAMPLE_RATE as f32;
+        let mut phase = 0.0;
+        let mut lfsr: u16 = 0x7fff;
+
+        for _ in 0..count {
+            let sample = match note.wave {
+                _ if note.frequency <= 0.0 => 0.0,
+                Wave::Square if phase < 0.5 => AMPLITUDE,
+                Wave::Square => -AMPLITUDE,
+                Wave::Noise if lfsr & 1 == 0 => AMPLITUDE,
+                Wave::Noise => -AMPLITUDE,
+            };
+            samples.push(sample);
+
+            phase += step;
+            while phase >= 1.0 {
+                phase -= 1.0;
+                let bit = (lfsr ^ (lfsr >> 1)) & 1;
+                lfsr = (lfsr >> 1) | (bit << 14);
+            }
+        }
+    }
+
+    samples
+}
+
+/// A synthesized sound, playable through `Audio<Panned<Beep>>`.
+#[derive(Debug, Clone)]
+pub struct Beep {
+    samples: Arc<[f32]>,
+}
+
+impl Beep {
+    pub fn new(notes: &[Note]) -> Self {
+        Self {
+            samples: render(notes).into(),
+        }
+    }
+
+    pub fn samples(&self) -> &[f32] {
+        &self.samples
+    }
+}
+
+pub struct BeepDecoder {
+    samples: Arc<[f32]>,
+    position: usize,
+}
+
+impl Iterator for BeepDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.samples.get(self.position).copied();
+        self.position += 1;
+        sample
+    }
+}
+
+impl Source for BeepDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        Some(self.samples.len().saturating_sub(self.position))
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(Duration::from_secs_f64(
+            self.samples.len() as f64 / SAMPLE_RATE as f64,
+        ))
+    }
+}
+
+impl Decodable for Beep {
+    type Decoder = BeepDecoder;
+    type DecoderItem = f32;
+
+    fn decoder(&self) -> BeepDecoder {
+        BeepDecoder {
+            samples: self.samples.clone(),
+            position: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Sfx {
+    LaneChange,
+    LevelUp,
+    Countdown,
+    Start,
+    /// A rival passing by.
+    Whoosh,
+}
+
+impl Sfx {
+    pub const ALL: [Self; 5] = [
+        Self::LaneChange,
+        Self::LevelUp,
+        Self::Countdown,
+        Self::Start,
+        Self::Whoosh,
+    ];
+
+    pub fn notes(&self) -> Vec<Note> {
+        match self {
+            Self::LaneChange => vec![Note::noise(4000.0, 25)],
+            Self::LevelUp => vec![
+                Note::square(523.25, 70),
+                Note::square(659.25, 70),
+                Note::square(783.99, 70),
+                Note::square(1046.5, 140),
+            ],
+            Self::Countdown => vec![Note::square(440.0, 120)],
+            Self::Start => vec![
+                Note::square(659.25, 100),
+                Note::square(659.25, 100),
+                Note::rest(50),
+                Note::square(783.99, 100),
+                Note::square(1046.5, 250),
+            ],
+            Self::Whoosh => vec![
+                Note::noise(1500.0, 30),
+                Note::noise(3000.0, 40),
+                Note::noise(6000.0, 30),
+                Note::noise(2500.0, 40),
+            ],
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct SynthSounds(HashMap<Sfx, Beep>);
+
+/// Renders the sound effects and plays them through the `StereoPlugin`
+/// sources, which has to be added too.
+pub struct SynthPlugin;
+
+impl Plugin for SynthPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Sfx>()
+            .add_event::<PassBy>()
+            .add_startup_system(setup_synth)
+            .add_system_to_stage(CoreStage::PostUpdate, play_sfx);
+    }
+}
+
+pub fn setup_synth(mut commands: Commands) {
+    let sounds = Sfx::ALL
+        .into_iter()
+        .map(|sfx| (sfx, Beep::new(&sfx.notes())))
+        .collect();
+
+    commands.insert_resource(SynthSounds(sounds));
+}
+
+/// Plays effects from the player's lane, and each pass-by from the lane of
+/// the rival.
+pub fn play_sfx(
+    mut sfx_events: EventReader<Sfx>,
+    mut passes: EventReader<PassBy>,
+    sounds: Res<SynthSounds>,
+    audio: Res<Audio<Panned<Beep>>>,
+    mut beeps: ResMut<Assets<Panned<Beep>>>,
+    settings: Res<Settings>,
+    mute: Res<MuteOverride>,
+    race: Res<Race>,
+    player: Query<&Car, With<Player>>,
+) {
+    let pan = player_pan(&race, &player);
+    let sfx = sfx_events.iter().map(|&sfx| (sfx, pan));
+    let passes = passes
+        .iter()
+        .map(|pass| (Sfx::Whoosh, lane_pan(race.config.lanes, pass.lane)));
+
+    for (sfx, pan) in sfx.chain(passes) {
+        let beep = Panned::new(sounds.0[&sfx].clone(), Pan::new(pan));
+        settings
+            .mixer
+            .play(&audio, Bus::Effects, beeps.add(beep), *mute);
+    }
+}
+
+/// Beeps counting down to the start of a run, which waits for it.
+#[derive(Resource)]
+pub struct Countdown {
+    timer: Timer,
+    beeps: u32,
+}
+
+impl Countdown {
+    pub fn is_running(&self) -> bool {
+        self.beeps <= COUNTDOWN_BEEPS
+    }
+}
+
+pub fn start_countdown(mut commands: Commands, mut sfx: EventWriter<Sfx>) {
+    commands.insert_resource(Countdown {
+        timer: Timer::new(COUNTDOWN_INTERVAL, TimerMode::Repeating),
+        beeps: 1,
+    });
+    sfx.send(Sfx::Countdown);
+}
+
+pub fn count_down(
+    countdown: Option<ResMut<Countdown>>,
+    timer: Res<Time>,
+    mut sfx: EventWriter<Sfx>,
+) {
+    let Some(mut countdown) = countdown else {
+        return;
+    };
+    if !countdown.is_running() || !countdown.timer.tick(timer.delta()).just_finished() {
+        return;
+    }
+
+    countdown.beeps += 1;
+    sfx.send(if countdown.is_running() {
+        Sfx::Countdown
+    } else {
+        Sfx::Start
+    });
+}
+
+pub fn level_up_jingle(race: Res<Race>, mut level: Local<usize>, mut sfx: EventWriter<Sfx>) {
+    if race.level() > *level && race.score > 0 {
+        sfx.send(Sfx::LevelUp);
+    }
+    *level = race.level();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn square_wave_alternates_every_half_period() {
+        // 2205 Hz is exactly ten samples per period.
+        let samples = render(&[Note::square(2205.0, 10)]);
+
+        assert_eq!(samples.len(), 221);
+        assert_eq!(samples[..5], [AMPLITUDE; 5]);
+        assert_eq!(samples[5..10], [-AMPLITUDE; 5]);
+        assert_eq!(samples[10..20], samples[..10]);
+    }
+
+    #[test]
+    fn notes_follow_each_other_and_rests_are_silent() {
+        let samples = render(&[Note::rest(20), Note::square(440.0, 20)]);
+        let (rest, tone) = samples.split_at(441);
+
+        assert_eq!(samples.len(), 882);
+        assert!(rest.iter().all(|&sample| sample == 0.0));
+        assert!(tone.iter().all(|&sample| sample.abs() == AMPLITUDE));
+    }
+
+    #[test]
+    fn noise_is_repeatable_but_not_periodic() {
+        let samples = render(&[Note::noise(SAMPLE_RATE as f32, 50)]);
+
+        assert_eq!(samples, render(&[Note::noise(SAMPLE_RATE as f32, 50)]));
+        assert!(samples.iter().all(|&sample| sample.abs() == AMPLITUDE));
+
+        let highs = samples.iter().filter(|&&sample| sample > 0.0).count();
+        assert!(highs > samples.len() / 3 && highs < samples.len() * 2 / 3);
+        assert_ne!(samples[..100], samples[100..200]);
+    }
+
+    #[test]
+    fn decoder_plays_the_rendered_samples() {
+        let beep = Beep::new(&Sfx::Countdown.notes());
+        let decoder = beep.decoder();
+
+        assert_eq!(decoder.channels(), 1);
+        let duration = decoder.total_duration().unwrap().as_secs_f64();
+        assert!((duration - 0.12).abs() < 1e-6);
+        assert_eq!(decoder.collect::<Vec<_>>(), beep.samples());
+    }
+}
@@ -1,5 +1,8 @@
 pub use crate::prelude::*;
-use std::cmp;
+use bevy::ecs::schedule::StateError;
+use bevy::input::keyboard::KeyboardInput;
+use bevy::input::ButtonState;
+use std::collections::HashSet;
 
 #[derive(Resource)]
 pub struct ExplosionSound(Handle<AudioSource>);
@@ -8,81 +11,150 @@ pub struct ExplosionSound(Handle<AudioSource>);
 pub struct MotorSound(Handle<AudioSource>);
 
 #[derive(Resource)]
-pub struct MotorController(Handle<AudioSink>);
-
-pub fn setup_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let button_entity = commands
-        .spawn(ButtonBundle {
-            style: Style {
-                size: Size::new(Val::Px(100.0), Val::Px(45.0)),
-                // center button
-                margin: UiRect::all(Val::Auto),
+pub struct MotorController {
+    pub sink: Handle<AudioSink>,
+    pub engine: EngineSound,
+    pub pan: Pan,
+}
+
+pub const MOTOR_VOLUME: f32 = 0.9;
+
+/// Whether pause was just pressed, consuming the press so the state we
+/// switch to does not see it in the same frame.
+fn take_pause(
+    bindings: &KeyBindings,
+    keyboard_input: &mut Input<KeyCode>,
+    gamepads: &Gamepads,
+    gamepad_input: &mut Input<GamepadButton>,
+) -> bool {
+    let key = bindings.clear_just_pressed(keyboard_input, InputAction::Pause);
+    let button = gamepad_clear_just_pressed(gamepads, gamepad_input, GamepadButtonType::Start);
+
+    key || button
+}
+
+/// Lets a state change queued after another in the same frame lose to it,
+/// as a pause pressed on the frame of a crash does. Any other error is a
+/// bug.
+pub fn queue_transition(result: Result<(), StateError>) {
+    match result {
+        Ok(()) | Err(StateError::StateAlreadyQueued) => {}
+        Err(err) => panic!("bad state change: {:?}", err),
+    }
+}
+
+pub fn pause_game(
+    mut state: ResMut<State<GameState>>,
+    race: Res<Race>,
+    bindings: Res<KeyBindings>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    mut gamepad_input: ResMut<Input<GamepadButton>>,
+) {
+    if race.is_over() {
+        return;
+    }
+
+    if take_pause(
+        &bindings,
+        &mut keyboard_input,
+        &gamepads,
+        &mut gamepad_input,
+    ) {
+        queue_transition(state.push(GameState::Paused));
+    }
+}
+
+pub fn resume_game(
+    mut state: ResMut<State<GameState>>,
+    bindings: Res<KeyBindings>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    mut gamepad_input: ResMut<Input<GamepadButton>>,
+) {
+    if take_pause(
+        &bindings,
+        &mut keyboard_input,
+        &gamepads,
+        &mut gamepad_input,
+    ) {
+        queue_transition(state.pop());
+    }
+}
+
+pub fn setup_pause(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    strings: Res<Strings>,
+    frame_query: Query<Entity, With<ScreenFrame>>,
+) {
+    let panel = PanelLayout::new();
+    let text_entity = commands
+        .spawn(
+            TextBundle::from_section(
+                strings.get("panel.pause"),
+                TextStyle {
+                    font: asset_server.load("fonts/Calculator.ttf"),
+                    font_size: FONT_SIZE,
+                    ..default()
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
                 position: UiRect {
-                    right: Val::Px((UI_WIDTH - WINDOW_PADDING) / 2.0),
+                    bottom: Val::Px(panel.pause),
+                    right: Val::Px(panel.right),
                     ..default()
                 },
-                justify_content: JustifyContent::Center,
-                align_items: AlignItems::Center,
                 ..default()
-            },
-            background_color: NORMAL_BUTTON.into(),
-            ..default()
-        })
-        .with_children(|parent| {
-            parent.spawn(TextBundle::from_section(
-                "Play",
-                TextStyle {
-                    font: asset_server.load("fonts/Calculator.ttf"),
-                    font_size: 20.0,
-                    color: Color::rgb(0.9, 0.9, 0.9),
-                },
-            ));
-        })
+            }),
+        )
+        .insert((Themed::Text, Localized("panel.pause")))
         .id();
-    commands.insert_resource(MenuData { button_entity });
+    for frame in &frame_query {
+        commands.entity(frame).add_child(text_entity);
+    }
+    commands.insert_resource(PauseData { text_entity });
 }
 
-pub fn menu(
-    mut state: ResMut<State<GameState>>,
-    keyboard_input: Res<Input<KeyCode>>,
-    mut interaction_query: Query<
-        (&Interaction, &mut BackgroundColor),
-        (Changed<Interaction>, With<Button>),
-    >,
-) {
-    for (interaction, mut color) in &mut interaction_query {
-        match *interaction {
-            Interaction::Clicked => {
-                *color = PRESSED_BUTTON.into();
-                state.set(GameState::Playing).unwrap();
-            }
-            Interaction::Hovered => {
-                *color = HOVERED_BUTTON.into();
-            }
-            Interaction::None => {
-                *color = NORMAL_BUTTON.into();
-            }
-        }
-    }
+pub fn cleanup_pause(mut commands: Commands, pause_data: Res<PauseData>) {
+    commands.entity(pause_data.text_entity).despawn_recursive();
+}
 
-    if keyboard_input.just_pressed(KeyCode::Space) {
-        state.set(GameState::Playing).unwrap();
+pub fn pause_motor_sound(
+    audio_sinks: Res<Assets<AudioSink>>,
+    motor_controller: Option<Res<MotorController>>,
+) {
+    if let Some(sink) = motor_controller.and_then(|motor| audio_sinks.get(&motor.sink)) {
+        sink.pause();
     }
 }
 
-pub fn cleanup_menu(mut commands: Commands, menu_data: Res<MenuData>) {
-    commands.entity(menu_data.button_entity).despawn_recursive();
+pub fn resume_motor_sound(
+    audio_sinks: Res<Assets<AudioSink>>,
+    motor_controller: Option<Res<MotorController>>,
+) {
+    if let Some(sink) = motor_controller.and_then(|motor| audio_sinks.get(&motor.sink)) {
+        sink.play();
+    }
 }
 
 pub fn setup(
     mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
     mut windows: ResMut<Windows>,
     asset_server: Res<AssetServer>,
     mut score_resource: ResMut<Scoreboard>,
+    race: Res<Race>,
+    layout: Res<ScreenLayout>,
+    strings: Res<Strings>,
 ) {
     let window = windows.primary_mut();
     window.center_window(MonitorSelection::Current);
-    commands.spawn(Camera2dBundle::default());
+
+    let mut camera = Camera2dBundle::default();
+    camera.transform.translation.x = TileScreen::camera_x(race.config.lanes);
+    commands.spawn(camera);
 
     let explosion_sound = asset_server.load("sounds/explosion.ogg");
     commands.insert_resource(ExplosionSound(explosion_sound));
@@ -93,261 +165,422 @@ pub fn setup(
     let text_style = TextStyle {
         font: asset_server.load("fonts/Calculator.ttf"),
         font_size: FONT_SIZE,
-        color: Color::BLACK,
+        ..default()
     };
 
     let text_alignment = TextAlignment::CENTER_RIGHT;
-
-    score_resource.entities.score = Some(
-        commands
+    let panel = PanelLayout::new();
+    let frame = spawn_screen_frame(&mut commands, &layout);
+    let mut children = Vec::new();
+
+    for (shows, key, digits, top) in [
+        (ScoreValue::Score, "panel.score", SCORE_DIGITS, panel.score),
+        (
+            ScoreValue::Highscore,
+            "panel.highscore",
+            SCORE_DIGITS,
+            panel.highscore,
+        ),
+        (ScoreValue::Speed, "panel.speed", SPEED_DIGITS, panel.speed),
+    ] {
+        let entity = commands
             .spawn(
                 TextBundle::from_sections([
-                    TextSection::new("SCORE\n", text_style.clone()),
-                    TextSection::new(score_resource.score.to_string(), text_style.clone()),
+                    TextSection::new(strings.get(key), text_style.clone()),
+                    TextSection::new("\n", text_style.clone()),
+                    TextSection::new("", text_style.clone()),
                 ])
                 .with_style(Style {
                     position_type: PositionType::Absolute,
                     display: Display::Flex,
                     justify_content: JustifyContent::FlexEnd,
                     position: UiRect {
-                        top: Val::Px(60.),
-                        right: Val::Px(20.),
+                        top: Val::Px(top),
+                        right: Val::Px(panel.right),
                         ..default()
                     },
                     ..default()
                 })
                 .with_text_alignment(text_alignment),
             )
-            .id(),
-    );
+            .insert((Themed::Text, Localized(key)))
+            .id();
+        children.push(entity);
+        children.push(spawn_segment_readout(
+            &mut commands,
+            &mut images,
+            shows,
+            digits,
+            PanelLayout::below_label(top),
+            panel.right,
+        ));
+
+        let entities = &mut score_resource.entities;
+        match shows {
+            ScoreValue::Score => entities.score = Some(entity),
+            ScoreValue::Highscore => entities.highscore = Some(entity),
+            ScoreValue::Speed => entities.speed = Some(entity),
+        }
+    }
 
-    score_resource.entities.highscore = Some(
+    children.push(
         commands
-            .spawn(
-                TextBundle::from_sections([
-                    TextSection::new("HISCORE\n", text_style.clone()),
-                    TextSection::new(score_resource.highscore.to_string(), text_style),
-                ])
-                .with_style(Style {
+            .spawn((
+                TextBundle::from_section(strings.get("panel.next"), text_style).with_style(Style {
                     position_type: PositionType::Absolute,
-                    display: Display::Flex,
-                    justify_content: JustifyContent::FlexEnd,
                     position: UiRect {
-                        top: Val::Px(120.),
-                        right: Val::Px(20.),
+                        top: Val::Px(panel.next),
+                        right: Val::Px(panel.right),
                         ..default()
                     },
                     ..default()
-                })
-                .with_text_alignment(text_alignment),
-            )
+                }),
+                Themed::Text,
+                Localized("panel.next"),
+            ))
             .id(),
     );
-
-    for x in 0..SCREEN_WIDTH {
-        for y in 0..SCREEN_HEIGHT {
-            commands.spawn(SpriteBundle {
-                sprite: Sprite {
-                    color: Color::rgba(0.0, 0.0, 0.0, 0.1),
-                    custom_size: Some(Vec2::new(TILE_SIZE, TILE_SIZE)),
-                    ..default()
-                },
-                transform: Transform {
-                    translation: Vec3::new(
-                        SCREEN_X + x as f32 * TILE_SIZE + HALF_TILE,
-                        SCREEN_Y + y as f32 * TILE_SIZE + HALF_TILE,
-                        0.0,
-                    ),
-                    scale: Vec3::new(0.8, 0.8, 1.0),
-                    ..default()
-                },
+    children.push(spawn_panel_tiles(
+        &mut commands,
+        &mut images,
+        PanelItem::Next,
+        PREVIEW_SIZE,
+        PREVIEW_TILE,
+        UiRect {
+            top: Val::Px(PanelLayout::below_label(panel.next)),
+            right: Val::Px(panel.right),
+            ..default()
+        },
+    ));
+    for (index, item) in [PanelItem::Sound, PanelItem::Pause].into_iter().enumerate() {
+        children.push(spawn_panel_tiles(
+            &mut commands,
+            &mut images,
+            item,
+            ICON_SIZE,
+            ICON_TILE,
+            UiRect {
+                bottom: Val::Px(panel.icons),
+                right: Val::Px(panel.icon_right(index)),
                 ..default()
-            });
-        }
+            },
+        ));
     }
+    commands.entity(frame).push_children(&children);
+
+    spawn_tile_layer(&mut commands, &mut images, race.config.lanes);
 }
 
+/// Starts the engine once its sound has loaded, so a run started straight
+/// away still gets it.
 pub fn play_motor_sound(
     mut commands: Commands,
     sound: Res<MotorSound>,
-    audio: Res<Audio>,
+    sources: Res<Assets<AudioSource>>,
+    audio: Res<Audio<Panned<AudioSource>>>,
+    mut panned: ResMut<Assets<Panned<AudioSource>>>,
     audio_sinks: Res<Assets<AudioSink>>,
+    motor_controller: Option<Res<MotorController>>,
+    settings: Res<Settings>,
+    mute: Res<MuteOverride>,
+    race: Res<Race>,
+    player: Query<&Car, With<Player>>,
 ) {
-    let handle = audio_sinks.get_handle(
-        audio.play_with_settings(sound.0.clone(), PlaybackSettings::LOOP.with_volume(0.9)),
+    let Some(source) = sources.get(&sound.0) else {
+        return;
+    };
+    if motor_controller.is_some() {
+        return;
+    }
+
+    // Plays even while muted, so unmuting brings the engine back.
+    let engine = EngineSound::new(&race);
+    let pan = Pan::new(player_pan(&race, &player));
+    let volume = MOTOR_VOLUME * settings.mixer.gain(Bus::Engine, *mute) * engine.volume;
+    let sink = audio_sinks.get_handle(
+        audio.play_with_settings(
+            panned.add(Panned::new(source.clone(), pan.clone())),
+            PlaybackSettings::LOOP
+                .with_volume(volume)
+                .with_speed(engine.speed),
+        ),
     );
 
-    commands.insert_resource(MotorController(handle));
+    commands.insert_resource(MotorController { sink, engine, pan });
 }
 
 pub fn play_explosion_sound(
     collision_events: EventReader<CollisionEvent>,
-    audio: Res<Audio>,
+    audio: Res<Audio<Panned<AudioSource>>>,
+    mut panned: ResMut<Assets<Panned<AudioSource>>>,
+    sources: Res<Assets<AudioSource>>,
     sound: Res<ExplosionSound>,
+    settings: Res<Settings>,
+    mute: Res<MuteOverride>,
+    mut music: ResMut<MusicMix>,
+    race: Res<Race>,
+    player: Query<&Car, With<Player>>,
 ) {
-    if !collision_events.is_empty() {
-        collision_events.clear();
-        audio.play(sound.0.clone());
+    if collision_events.is_empty() {
+        return;
     }
-}
-
-pub fn enemy_respawn(mut enemies_query: Query<&mut Transform, With<Enemy>>) {
-    for mut enemy_transform in enemies_query.iter_mut() {
-        if enemy_transform.translation.y < PLAYER_Y - TILE_SIZE * (CAR_SPACING - 1.0) {
-            let mut rng = thread_rng();
-            let column = rng.gen_range(0..3);
-
-            let pos_y = SCREEN_Y + SCREEN_HEIGHT as f32 * TILE_SIZE + TILE_SIZE * CAR_SPACING;
-            enemy_transform.translation.y = pos_y;
+    collision_events.clear();
+    music.duck();
 
-            enemy_transform.translation.x = TileScreen::column_to_coord(column);
-        }
+    if let Some(source) = sources.get(&sound.0) {
+        let pan = Pan::new(player_pan(&race, &player));
+        let explosion = panned.add(Panned::new(source.clone(), pan));
+        settings.mixer.play(&audio, Bus::Effects, explosion, *mute);
     }
 }
 
-pub fn wall_respawn(mut walls_query: Query<&mut Transform, With<Wall>>) {
-    for mut wall_transform in walls_query.iter_mut() {
-        if wall_transform.translation.y < PLAYER_Y - TILE_SIZE * (WALL_SPACING + 1.0) {
-            let pos_y = SCREEN_Y + SCREEN_HEIGHT as f32 * TILE_SIZE + TILE_SIZE * WALL_SPACING;
-            wall_transform.translation.y = pos_y;
-        }
-    }
+#[derive(SystemLabel)]
+pub enum RaceLabel {
+    Input,
+    Rules,
+}
+
+/// Systems shared by every run on the road, whoever is driving. The caller
+/// adds the system that advances the race, labelled `RaceLabel::Rules`.
+pub fn race_systems(state: GameState) -> SystemSet {
+    SystemSet::on_update(state)
+        .with_system(position_player.after(RaceLabel::Rules))
+        .with_system(position_enemies.after(RaceLabel::Rules))
+        .with_system(position_walls.after(RaceLabel::Rules))
 }
-pub fn accelerate(
-    mut query: Query<&mut Transform, With<MoveY>>,
-    mut game_data: ResMut<GameData>,
-    timer: Res<Time>,
-) {
-    let delta = timer.delta();
-    let boost_factor = game_data.boost_factor;
-    let speed_factor = game_data.speed_factor;
-
-    if game_data.is_boosting {
-        game_data
-            .move_timer
-            .tick(delta.mul_f32(boost_factor * speed_factor));
-    } else {
-        game_data.move_timer.tick(delta.mul_f32(speed_factor));
-    }
 
-    if !game_data.move_timer.finished() {
+pub fn accelerate(mut race: ResMut<Race>, timer: Res<Time>, countdown: Option<Res<Countdown>>) {
+    if countdown.is_some_and(|countdown| countdown.is_running()) {
         return;
     }
 
-    for mut entity_transform in query.iter_mut() {
-        entity_transform.translation.y -= TILE_SIZE;
-    }
+    race.update(timer.delta());
 }
 
-pub fn boost_player(keyboard_input: Res<Input<KeyCode>>, mut game_data: ResMut<GameData>) {
-    if keyboard_input.just_pressed(KeyCode::Space) {
-        game_data.is_boosting = true;
+pub fn boost_player(
+    keyboard_input: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut race: ResMut<Race>,
+) {
+    if bindings.just_pressed(&keyboard_input, InputAction::Boost) {
+        race.is_boosting = true;
     }
 
-    if keyboard_input.just_released(KeyCode::Space) {
-        game_data.is_boosting = false;
+    if bindings.just_released(&keyboard_input, InputAction::Boost) {
+        race.is_boosting = false;
     }
 }
 
+/// Reads key presses from the event stream rather than `just_pressed`, so two
+/// taps of the same key between frames both count. OS key repeat is ignored,
+/// holding is handled by the `SteerQueue` auto-repeat.
 pub fn move_player(
+    mut key_events: EventReader<KeyboardInput>,
     keyboard_input: Res<Input<KeyCode>>,
-    mut query: Query<(&mut Car, &mut Transform), With<Player>>,
+    bindings: Res<KeyBindings>,
+    mut held_keys: Local<HashSet<KeyCode>>,
+    mut steering: ResMut<SteerQueue>,
     state: Res<State<GameState>>,
 ) {
     if *state.current() != GameState::Playing {
         return;
     }
 
-    let (mut car, mut player_transform) = query.single_mut();
-    let mut direction = 0.0;
+    // Releases can happen while this system is not running.
+    held_keys.retain(|&key| keyboard_input.pressed(key));
+
+    let steers = [
+        (InputAction::SteerLeft, Steer::Left),
+        (InputAction::SteerRight, Steer::Right),
+    ];
+
+    for event in key_events.iter() {
+        let Some(key) = event.key_code else {
+            continue;
+        };
+
+        match event.state {
+            ButtonState::Pressed if held_keys.insert(key) => {
+                for (action, steer) in steers {
+                    if bindings.keys(action).contains(&key) {
+                        steering.press(steer);
+                    }
+                }
+            }
+            ButtonState::Released => {
+                held_keys.remove(&key);
+            }
+            _ => {}
+        }
+    }
 
-    if keyboard_input.any_just_pressed([KeyCode::Left, KeyCode::A]) && car.column > 0 {
-        direction -= COLUMN_SIZE;
-        car.column -= 1;
+    for (action, steer) in steers {
+        if bindings.pressed(&keyboard_input, action) {
+            steering.hold(steer);
+        }
     }
+}
 
-    if keyboard_input.any_just_pressed([KeyCode::Right, KeyCode::D]) && car.column < 2 {
-        direction += COLUMN_SIZE;
-        car.column += 1;
+pub fn position_player(
+    race: Res<Race>,
+    mut query: Query<(&mut Car, &mut Transform), With<Player>>,
+) {
+    for (mut car, mut transform) in query.iter_mut() {
+        car.column = race.lane;
+        transform.translation.x = TileScreen::column_to_coord(race.lane);
     }
+}
+
+pub fn position_enemies(race: Res<Race>, mut query: Query<(&Enemy, &mut Car, &mut Transform)>) {
+    for (enemy, mut car, mut transform) in query.iter_mut() {
+        let rival = race.rivals[enemy.0];
 
-    player_transform.translation.x += direction;
+        car.column = rival.lane;
+        transform.translation.x = TileScreen::column_to_coord(rival.lane);
+        transform.translation.y = TileScreen::car_coord(rival.row);
+    }
+}
+
+pub fn position_walls(race: Res<Race>, mut query: Query<(&Wall, &mut Transform)>) {
+    for (wall, mut transform) in query.iter_mut() {
+        transform.translation.y = TileScreen::wall_coord(race.walls[wall.0]);
+    }
 }
 
 pub fn check_collisions(
     mut commands: Commands,
-    player_query: Query<(Entity, &Transform), With<Player>>,
-    enemies_query: Query<(Entity, &Transform), With<Enemy>>,
-    walls_query: Query<(Entity, &Transform), With<Wall>>,
+    player_query: Query<Entity, With<Player>>,
+    enemies_query: Query<Entity, With<Enemy>>,
+    walls_query: Query<Entity, With<Wall>>,
     mut scoreboard: ResMut<Scoreboard>,
     mut collision_events: EventWriter<CollisionEvent>,
     mut state: ResMut<State<GameState>>,
-    mut game_data: ResMut<GameData>,
+    mut race: ResMut<Race>,
     audio_sinks: Res<Assets<AudioSink>>,
-    motor_controller: Res<MotorController>,
+    motor_controller: Option<Res<MotorController>>,
 ) {
-    let (player_entity, player_transform) = player_query.single();
-    let top_bound = PLAYER_Y + TILE_SIZE * 4.0;
-    let bottom_bound = PLAYER_Y - TILE_SIZE * 4.0 - HALF_TILE;
-
-    for (_, enemy_transform) in &enemies_query {
-        let enemy_y = enemy_transform.translation.y;
-        let enemy_x = enemy_transform.translation.x;
-
-        if enemy_y < top_bound
-            && enemy_y > bottom_bound
-            && enemy_x == player_transform.translation.x
-        {
-            collision_events.send_default();
-
-            for (entity, _) in &walls_query {
-                commands.entity(entity).despawn_recursive();
-            }
+    if !race.is_over() {
+        return;
+    }
 
-            for (entity, _) in &enemies_query {
-                commands.entity(entity).despawn_recursive();
-            }
+    collision_events.send_default();
 
-            commands.entity(player_entity).despawn_recursive();
+    for entity in walls_query
+        .iter()
+        .chain(&enemies_query)
+        .chain(&player_query)
+    {
+        commands.entity(entity).despawn_recursive();
+    }
 
-            scoreboard.highscore = cmp::max(scoreboard.highscore, scoreboard.score);
-            scoreboard.score = 0;
-            game_data.speed_factor = 1.0;
+    scoreboard.record(race.score);
+    race.restart();
 
-            if let Some(sink) = audio_sinks.get(&motor_controller.0) {
-                sink.pause();
-                commands.remove_resource::<MotorController>();
-            }
+    if let Some(sink) = motor_controller.and_then(|motor| audio_sinks.get(&motor.sink)) {
+        sink.pause();
+        commands.remove_resource::<MotorController>();
+    }
 
-            state.set(GameState::Menu).unwrap();
+    state.set(GameState::Menu).unwrap();
+}
+
+/// Shows the scores as text, or on the seven-segment readouts when those
+/// are turned on.
+pub fn update_scoreboard(
+    settings: Res<Settings>,
+    race: Res<Race>,
+    score_resource: Res<Scoreboard>,
+    mut score_query: Query<&mut Text>,
+    mut readouts: Query<(&mut SegmentReadout, &mut Style)>,
+) {
+    let value = |shows| match shows {
+        ScoreValue::Score => race.score,
+        ScoreValue::Highscore => score_resource.highscore,
+        ScoreValue::Speed => race.level(),
+    };
+
+    let entities = &score_resource.entities;
+    for (entity, shows) in [
+        (entities.score, ScoreValue::Score),
+        (entities.highscore, ScoreValue::Highscore),
+        (entities.speed, ScoreValue::Speed),
+    ] {
+        let Some(mut text) = entity.and_then(|entity| score_query.get_mut(entity).ok()) else {
+            continue;
+        };
+        let shown = if settings.segment_digits {
+            String::new()
+        } else {
+            value(shows).to_string()
+        };
+        if text.sections[2].value != shown {
+            text.sections[2].value = shown;
         }
     }
-}
 
-pub fn update_scoreboard(score_resource: Res<Scoreboard>, mut score_query: Query<&mut Text>) {
-    score_query
-        .get_mut(score_resource.entities.score.unwrap())
-        .unwrap()
-        .sections[1]
-        .value = score_resource.score.to_string();
-
-    score_query
-        .get_mut(score_resource.entities.highscore.unwrap())
-        .unwrap()
-        .sections[1]
-        .value = score_resource.highscore.to_string();
+    let display = if settings.segment_digits {
+        Display::Flex
+    } else {
+        Display::None
+    };
+    for (mut readout, mut style) in &mut readouts {
+        let value = value(readout.shows);
+        if readout.value != value {
+            readout.value = value;
+        }
+        if style.display != display {
+            style.display = display;
+        }
+    }
 }
 
-pub fn increment_scoreboard(
-    mut scoreboard: ResMut<Scoreboard>,
-    mut game_data: ResMut<GameData>,
-    state: Res<State<GameState>>,
-) {
-    if *state.current() != GameState::Playing {
-        return;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::input::InputPlugin;
+
+    #[test]
+    fn pausing_on_the_crash_frame_leaves_for_the_menu() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin::default())
+            .add_plugin(InputPlugin)
+            .add_asset::<AudioSink>()
+            .add_state(GameState::Playing)
+            .init_resource::<Race>()
+            .init_resource::<Scoreboard>()
+            .init_resource::<KeyBindings>()
+            .add_event::<CollisionEvent>()
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(check_collisions)
+                    .with_system(pause_game.after(check_collisions)),
+            );
+
+        app.world.resource_mut::<Race>().crash = Some(Crash {
+            cause: CrashCause::Rammed,
+            lane: 0,
+            boosting: false,
+        });
+        app.world.send_event(KeyboardInput {
+            scan_code: 0,
+            key_code: Some(KeyCode::Escape),
+            state: ButtonState::Pressed,
+        });
+        app.update();
+
+        assert_eq!(
+            *app.world.resource::<State<GameState>>().current(),
+            GameState::Menu
+        );
     }
-    scoreboard.score += 100;
 
-    let factor = (scoreboard.score as f32 / 1000.0).floor() * 0.02 + 1.0;
-    game_data.speed_factor = factor;
+    #[test]
+    fn only_a_queued_change_is_ignored() {
+        queue_transition(Ok(()));
+        queue_transition(Err(StateError::StateAlreadyQueued));
+        assert!(
+            std::panic::catch_unwind(|| queue_transition(Err(StateError::StackEmpty))).is_err()
+        );
+    }
 }
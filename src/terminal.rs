@@ -0,0 +1,132 @@
+use crate::prelude::*;
+
+/// A lit tile, two columns wide so tiles come out roughly square.
+pub const LIT_CELL: &str = "██";
+pub const UNLIT_CELL: &str = "░░";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerminalKey {
+    Left,
+    Right,
+    /// Space, toggling boost since terminals do not report key releases.
+    Boost,
+    Pause,
+    Quit,
+}
+
+/// Keys in a chunk of raw terminal input. Arrows come as `ESC [ C` or, in
+/// application mode, `ESC O C`; a lone escape quits.
+pub fn parse_keys(input: &[u8]) -> Vec<TerminalKey> {
+    let mut keys = Vec::new();
+    let mut bytes = input.iter().copied().peekable();
+
+    while let Some(byte) = bytes.next() {
+        let key = match byte {
+            0x1b => match (bytes.peek().copied(), input.len()) {
+                (Some(b'[' | b'O'), _) => {
+                    bytes.next();
+                    match bytes.next() {
+                        Some(b'D') => Some(TerminalKey::Left),
+                        Some(b'C') => Some(TerminalKey::Right),
+                        _ => None,
+                    }
+                }
+                (None, 1) => Some(TerminalKey::Quit),
+                _ => None,
+            },
+            b'a' | b'A' => Some(TerminalKey::Left),
+            b'd' | b'D' => Some(TerminalKey::Right),
+            b' ' => Some(TerminalKey::Boost),
+            b'p' | b'P' => Some(TerminalKey::Pause),
+            // Ctrl-C, since raw mode turns the signal off.
+            b'q' | b'Q' | 0x03 => Some(TerminalKey::Quit),
+            _ => None,
+        };
+        keys.extend(key);
+    }
+
+    keys
+}
+
+/// One screen of the game: the tile grid with the score panel beside it,
+/// then a status line. Lines end in "clear to end of line" so redrawing over
+/// the last frame leaves nothing behind.
+pub fn draw_frame(race: &Race, highscore: usize, paused: bool) -> String {
+    let grid = TileGrid::from_race(race);
+    let panel = [
+        String::new(),
+        "SCORE".to_string(),
+        race.score.to_string(),
+        String::new(),
+        "HISCORE".to_string(),
+        highscore.max(race.score).to_string(),
+        String::new(),
+        "LEVEL".to_string(),
+        race.level().to_string(),
+        String::new(),
+        if race.is_boosting { "BOOST" } else { "" }.to_string(),
+    ];
+
+    let mut frame = String::new();
+    for (y, row) in grid.rows().enumerate() {
+        for &lit in row {
+            frame.push_str(if lit { LIT_CELL } else { UNLIT_CELL });
+        }
+        frame.push_str("  ");
+        frame.push_str(panel.get(y).map_or("", String::as_str));
+        frame.push_str("\x1b[K\r\n");
+    }
+
+    frame.push_str(if race.is_over() {
+        "GAME OVER  SPACE: AGAIN  Q: QUIT"
+    } else if paused {
+        "PAUSE  P: RESUME  Q: QUIT"
+    } else {
+        "ARROWS: STEER  SPACE: BOOST  P: PAUSE  Q: QUIT"
+    });
+    frame.push_str("\x1b[K");
+
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arrows_and_letters_become_keys() {
+        assert_eq!(
+            parse_keys(b"\x1b[D\x1bOC p q"),
+            [
+                TerminalKey::Left,
+                TerminalKey::Right,
+                TerminalKey::Boost,
+                TerminalKey::Pause,
+                TerminalKey::Boost,
+                TerminalKey::Quit,
+            ]
+        );
+        assert_eq!(parse_keys(b"\x1b"), [TerminalKey::Quit]);
+        assert_eq!(parse_keys(b"\x1b[A"), []);
+    }
+
+    #[test]
+    fn frame_draws_the_grid_beside_the_panel() {
+        let race = Race::default();
+        let frame = draw_frame(&race, 4200, false);
+        let lines: Vec<&str> = frame.split("\r\n").collect();
+
+        assert_eq!(lines.len(), SCREEN_HEIGHT + 1);
+        assert!(lines[1].ends_with("SCORE\x1b[K"));
+        assert!(lines[5].ends_with("4200\x1b[K"));
+
+        // The player's car fills the bottom row at its wheels.
+        let bottom = TileGrid::from_race(&race).rows().last().unwrap().to_vec();
+        let drawn: String = bottom
+            .iter()
+            .map(|&lit| if lit { LIT_CELL } else { UNLIT_CELL })
+            .collect();
+        assert!(lines[SCREEN_HEIGHT - 1].starts_with(&drawn));
+        assert!(drawn.contains(LIT_CELL));
+    }
+}
@@ -0,0 +1,198 @@
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+pub enum ThemePreset {
+    /// The green LCD of the original handheld.
+    #[default]
+    Classic,
+    /// The four greens of the DMG Game Boy.
+    Dmg,
+    Amber,
+    HighContrast,
+    Dark,
+}
+
+impl ThemePreset {
+    pub const ALL: [Self; 5] = [
+        Self::Classic,
+        Self::Dmg,
+        Self::Amber,
+        Self::HighContrast,
+        Self::Dark,
+    ];
+
+    /// Key of the preset's name in the string tables.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Self::Classic => "theme.classic",
+            Self::Dmg => "theme.dmg",
+            Self::Amber => "theme.amber",
+            Self::HighContrast => "theme.contrast",
+            Self::Dark => "theme.dark",
+        }
+    }
+}
+
+fn hex(color: &str) -> Color {
+    Color::hex(color).unwrap()
+}
+
+/// Every color the game draws with, picked from a preset.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    pub background: Color,
+    /// A lit tile.
+    pub tile: Color,
+    /// An unlit tile, the faint grid behind the road.
+    pub cell: Color,
+    /// Text drawn straight on the background.
+    pub text: Color,
+    pub button: Color,
+    pub button_hovered: Color,
+    pub button_pressed: Color,
+    pub button_text: Color,
+}
+
+impl Theme {
+    pub fn preset(preset: ThemePreset) -> Self {
+        match preset {
+            ThemePreset::Classic => Self {
+                background: hex("8d9e7b"),
+                tile: Color::rgba(0.0, 0.0, 0.0, 0.98),
+                cell: Color::rgba(0.0, 0.0, 0.0, 0.1),
+                text: Color::BLACK,
+                button: Color::rgb(0.15, 0.15, 0.15),
+                button_hovered: Color::rgb(0.25, 0.25, 0.25),
+                button_pressed: Color::rgb(0.35, 0.75, 0.35),
+                button_text: Color::rgb(0.9, 0.9, 0.9),
+            },
+            ThemePreset::Dmg => Self {
+                background: hex("9bbc0f"),
+                tile: hex("0f380f"),
+                cell: hex("8bac0f"),
+                text: hex("0f380f"),
+                button: hex("306230"),
+                button_hovered: hex("0f380f"),
+                button_pressed: hex("8bac0f"),
+                button_text: hex("9bbc0f"),
+            },
+            ThemePreset::Amber => Self {
+                background: hex("1a1000"),
+                tile: hex("ffb000"),
+                cell: hex("2e1d00"),
+                text: hex("ffb000"),
+                button: hex("3a2400"),
+                button_hovered: hex("5c3a00"),
+                button_pressed: hex("8a5800"),
+                button_text: hex("ffcc66"),
+            },
+            ThemePreset::HighContrast => Self {
+                background: Color::WHITE,
+                tile: Color::BLACK,
+                cell: hex("e8e8e8"),
+                text: Color::BLACK,
+                button: Color::BLACK,
+                button_hovered: hex("0040ff"),
+                button_pressed: hex("ff0000"),
+                button_text: Color::WHITE,
+            },
+            ThemePreset::Dark => Self {
+                background: hex("1c211d"),
+                tile: hex("c8d4c0"),
+                cell: hex("262d28"),
+                text: hex("c8d4c0"),
+                button: hex("2f3831"),
+                button_hovered: hex("47544a"),
+                button_pressed: hex("4f8a4f"),
+                button_text: hex("e6ede2"),
+            },
+        }
+    }
+
+    /// A cell `intensity` of the way from unlit to lit.
+    pub fn lit(&self, intensity: f32) -> Color {
+        let cell = Vec4::from(self.cell.as_rgba_f32());
+        let tile = Vec4::from(self.tile.as_rgba_f32());
+        let [r, g, b, a] = cell.lerp(tile, intensity).to_array();
+
+        Color::rgba(r, g, b, a)
+    }
+
+    pub fn color(&self, role: Themed) -> Color {
+        match role {
+            Themed::Background => self.background,
+            Themed::Text => self.text,
+            Themed::Button => self.button,
+            Themed::ButtonText => self.button_text,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::preset(ThemePreset::default())
+    }
+}
+
+/// Which theme color a text or UI node is drawn in, so it follows
+/// theme changes.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Themed {
+    Background,
+    Text,
+    Button,
+    ButtonText,
+}
+
+/// Colors new themed entities, and all of them when the theme changes.
+pub fn apply_theme(
+    theme: Res<Theme>,
+    mut texts: Query<(&Themed, ChangeTrackers<Themed>, &mut Text)>,
+    mut nodes: Query<(&Themed, ChangeTrackers<Themed>, &mut BackgroundColor)>,
+) {
+    let recolor = |added: ChangeTrackers<Themed>| theme.is_changed() || added.is_added();
+
+    for (&themed, added, mut text) in &mut texts {
+        if recolor(added) {
+            for section in &mut text.sections {
+                section.style.color = theme.color(themed);
+            }
+        }
+    }
+    for (&themed, added, mut color) in &mut nodes {
+        if recolor(added) {
+            *color = theme.color(themed).into();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn themes_recolor_live() {
+        let mut app = App::new();
+        app.insert_resource(Theme::default())
+            .add_system(apply_theme);
+
+        let screen = app
+            .world
+            .spawn((BackgroundColor::default(), Themed::Background))
+            .id();
+        app.update();
+        assert_eq!(
+            app.world.get::<BackgroundColor>(screen).unwrap().0,
+            Theme::default().background
+        );
+
+        let amber = Theme::preset(ThemePreset::Amber);
+        *app.world.resource_mut::<Theme>() = amber;
+        app.update();
+        assert_eq!(
+            app.world.get::<BackgroundColor>(screen).unwrap().0,
+            amber.background
+        );
+    }
+}
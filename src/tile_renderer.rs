@@ -0,0 +1,221 @@
+use crate::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::render::texture::ImageSampler;
+
+/// Image pixels per playfield cell, one per world unit.
+pub const TILE_PIXELS: usize = TILE_SIZE as usize;
+/// Side of the square drawn in each cell; the rest is the gap between cells.
+pub const TILE_FILL: usize = 16;
+
+const TILE_MARGIN: usize = (TILE_PIXELS - TILE_FILL) / 2;
+pub const BYTES_PER_PIXEL: usize = 4;
+
+/// `color` as the bytes of an sRGB pixel.
+pub fn rgba8(color: Color) -> [u8; BYTES_PER_PIXEL] {
+    color
+        .as_rgba_f32()
+        .map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8)
+}
+
+/// Writes a `width` x `height` cell grid into RGBA pixels, top row first.
+/// Cells are counted from the bottom like `TileGrid` rows, and each is drawn
+/// `intensity` of the way from unlit to lit on a transparent background.
+pub fn rasterize(
+    pixels: &mut [u8],
+    width: usize,
+    height: usize,
+    theme: &Theme,
+    intensity: impl Fn(usize, usize) -> f32,
+) {
+    rasterize_cells(pixels, width, height, theme, |x, y| Some(intensity(x, y)));
+}
+
+/// Like `rasterize`, but cells without an intensity are left out entirely.
+pub fn rasterize_cells(
+    pixels: &mut [u8],
+    width: usize,
+    height: usize,
+    theme: &Theme,
+    intensity: impl Fn(usize, usize) -> Option<f32>,
+) {
+    let stride = width * TILE_PIXELS * BYTES_PER_PIXEL;
+    assert_eq!(pixels.len(), stride * height * TILE_PIXELS);
+
+    pixels.fill(0);
+    for y in 0..height {
+        let top = (height - 1 - y) * TILE_PIXELS + TILE_MARGIN;
+
+        for x in 0..width {
+            let Some(intensity) = intensity(x, y) else {
+                continue;
+            };
+            let color = rgba8(theme.lit(intensity));
+            let left = (x * TILE_PIXELS + TILE_MARGIN) * BYTES_PER_PIXEL;
+
+            for row in top..top + TILE_FILL {
+                let start = row * stride + left;
+                for pixel in pixels[start..start + TILE_FILL * BYTES_PER_PIXEL]
+                    .chunks_exact_mut(BYTES_PER_PIXEL)
+                {
+                    pixel.copy_from_slice(&color);
+                }
+            }
+        }
+    }
+}
+
+/// An empty `width` x `height` cell texture, sampled without smoothing.
+pub fn tile_image(width: usize, height: usize) -> Image {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: (width * TILE_PIXELS) as u32,
+            height: (height * TILE_PIXELS) as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0; BYTES_PER_PIXEL],
+        TextureFormat::Rgba8UnormSrgb,
+    );
+    image.sampler_descriptor = ImageSampler::nearest();
+
+    image
+}
+
+/// The playfield drawn as a single textured quad, `width` x `height` cells.
+#[derive(Component)]
+pub struct TileLayer {
+    pub image: Handle<Image>,
+    pub width: usize,
+    pub height: usize,
+}
+
+pub fn spawn_tile_layer(commands: &mut Commands, images: &mut Assets<Image>, lanes: usize) {
+    let (width, height) = (TileScreen::screen_width(lanes), SCREEN_HEIGHT);
+    let size = Vec2::new(width as f32, height as f32) * TILE_SIZE;
+
+    let image = images.add(tile_image(width, height));
+
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(size),
+                ..default()
+            },
+            texture: image.clone(),
+            transform: Transform::from_xyz(SCREEN_X + size.x / 2.0, SCREEN_Y + size.y / 2.0, 0.0),
+            ..default()
+        },
+        TileLayer {
+            image,
+            width,
+            height,
+        },
+    ));
+}
+
+/// Redraws the tile layer from the cars and walls on screen, fading cells
+/// out instead when ghosting is on.
+pub fn render_tiles(
+    settings: Res<Settings>,
+    theme: Res<Theme>,
+    race: Res<Race>,
+    timer: Res<Time>,
+    mut persistence: ResMut<LcdPersistence>,
+    mut images: ResMut<Assets<Image>>,
+    layer_query: Query<&TileLayer>,
+    player_query: Query<&Car, With<Player>>,
+    enemies_query: Query<&Enemy>,
+    walls_query: Query<&Wall>,
+) {
+    for layer in &layer_query {
+        let mut grid = TileGrid::new(layer.width, layer.height);
+        for wall in &walls_query {
+            grid.draw_walls(race.walls[wall.0]);
+        }
+        for car in &player_query {
+            grid.draw_car(car.column, 0);
+        }
+        for enemy in &enemies_query {
+            let rival = race.rivals[enemy.0];
+            grid.draw_car(rival.lane, rival.row);
+        }
+
+        let Some(image) = images.get_mut(&layer.image) else {
+            continue;
+        };
+        if settings.ghosting {
+            persistence.update(&grid, timer.delta());
+            rasterize(&mut image.data, grid.width, grid.height, &theme, |x, y| {
+                persistence.intensity(x, y)
+            });
+        } else {
+            rasterize(&mut image.data, grid.width, grid.height, &theme, |x, y| {
+                grid.get(x, y) as u8 as f32
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixel(pixels: &[u8], width: usize, x: usize, y: usize) -> [u8; 4] {
+        let start = (y * width * TILE_PIXELS + x) * BYTES_PER_PIXEL;
+        pixels[start..start + BYTES_PER_PIXEL].try_into().unwrap()
+    }
+
+    #[test]
+    fn cells_fill_a_square_with_gaps_between() {
+        let theme = Theme::default();
+        let (width, height) = (2, 3);
+        let mut pixels = vec![0; width * height * TILE_PIXELS * TILE_PIXELS * BYTES_PER_PIXEL];
+
+        // Only the bottom-left cell is lit.
+        rasterize(&mut pixels, width, height, &theme, |x, y| {
+            (x == 0 && y == 0) as u8 as f32
+        });
+
+        let bottom = 2 * TILE_PIXELS;
+        let lit = rgba8(theme.tile);
+        let unlit = rgba8(theme.cell);
+
+        assert_eq!(
+            pixel(&pixels, width, TILE_MARGIN, bottom + TILE_MARGIN),
+            lit
+        );
+        assert_eq!(
+            pixel(
+                &pixels,
+                width,
+                TILE_MARGIN + TILE_FILL - 1,
+                bottom + TILE_MARGIN + TILE_FILL - 1
+            ),
+            lit
+        );
+        assert_eq!(
+            pixel(&pixels, width, TILE_MARGIN - 1, bottom + TILE_MARGIN),
+            [0; 4]
+        );
+        assert_eq!(
+            pixel(&pixels, width, TILE_MARGIN + TILE_FILL, bottom + 5),
+            [0; 4]
+        );
+
+        assert_eq!(pixel(&pixels, width, TILE_PIXELS + 5, bottom + 5), unlit);
+        assert_eq!(pixel(&pixels, width, 5, 5), unlit);
+    }
+
+    #[test]
+    fn intensity_blends_from_unlit_to_lit() {
+        let theme = Theme::preset(ThemePreset::HighContrast);
+        let mut pixels = vec![0; TILE_PIXELS * TILE_PIXELS * BYTES_PER_PIXEL];
+
+        rasterize(&mut pixels, 1, 1, &theme, |_, _| 0.5);
+
+        let centre = pixel(&pixels, 1, TILE_PIXELS / 2, TILE_PIXELS / 2);
+        assert_eq!(centre, rgba8(theme.lit(0.5)));
+        assert!(centre[0] < rgba8(theme.cell)[0]);
+        assert!(centre[0] > rgba8(theme.tile)[0]);
+    }
+}
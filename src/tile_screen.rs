@@ -1,9 +1,28 @@
 use crate::prelude::*;
 
+/// Car sprite, top row first.
+pub const CAR: [&str; 4] = ["_O_", "OOO", "_O_", "O_O"];
+pub const WALL_HEIGHT: usize = 3;
+pub const LANE_WIDTH: usize = 3;
+
 #[derive(Default)]
 pub struct TileScreen {}
 
 impl TileScreen {
+    pub fn screen_width(lanes: usize) -> usize {
+        lanes * LANE_WIDTH + PADDING * 2
+    }
+
+    pub fn window_width(lanes: usize) -> f32 {
+        UI_WIDTH + Self::screen_width(lanes) as f32 * TILE_SIZE + WINDOW_PADDING * 2.0
+    }
+
+    /// Camera offset that keeps the playfield's left edge put when extra
+    /// lanes widen the window.
+    pub fn camera_x(lanes: usize) -> f32 {
+        (Self::window_width(lanes) - WINDOW_WIDTH) / 2.0
+    }
+
     pub fn column_to_coord(column: usize) -> f32 {
         let padding = PADDING as f32;
         let column = column as f32;
@@ -11,7 +30,87 @@ impl TileScreen {
         SCREEN_X + (column * COLUMN_SIZE) + (HALF_TILE * 3.0) + TILE_SIZE * padding
     }
 
-    pub fn tile_scale() -> Vec3 {
-        Vec3::new(0.85, 0.85, 0.0)
+    pub fn row_to_coord(row: i32) -> f32 {
+        SCREEN_Y + row as f32 * TILE_SIZE
+    }
+
+    /// Anchor of a car whose bottom row is `row`.
+    pub fn car_coord(row: i32) -> f32 {
+        Self::row_to_coord(row) + TILE_SIZE * 2.0
+    }
+
+    /// Anchor of a wall segment whose bottom row is `row`.
+    pub fn wall_coord(row: i32) -> f32 {
+        Self::row_to_coord(row) + TILE_SIZE
+    }
+}
+
+/// The lit cells of the playfield, row 0 at the bottom like `Race` rows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TileGrid {
+    pub width: usize,
+    pub height: usize,
+    cells: Vec<bool>,
+}
+
+impl TileGrid {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![false; width * height],
+        }
+    }
+
+    pub fn from_race(race: &Race) -> Self {
+        let mut grid = Self::new(TileScreen::screen_width(race.config.lanes), SCREEN_HEIGHT);
+
+        for &row in &race.walls {
+            grid.draw_walls(row);
+        }
+
+        grid.draw_car(race.lane, 0);
+        for rival in &race.rivals {
+            grid.draw_car(rival.lane, rival.row);
+        }
+
+        grid
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        self.cells[y * self.width + x]
+    }
+
+    /// Lights a cell, ignoring anything outside the grid.
+    pub fn light(&mut self, x: i32, y: i32) {
+        if (0..self.width as i32).contains(&x) && (0..self.height as i32).contains(&y) {
+            self.cells[y as usize * self.width + x as usize] = true;
+        }
+    }
+
+    /// Both wall segments whose bottom row is `row`.
+    pub fn draw_walls(&mut self, row: i32) {
+        for y in row..row + WALL_HEIGHT as i32 {
+            self.light(0, y);
+            self.light(self.width as i32 - 1, y);
+        }
+    }
+
+    pub fn draw_car(&mut self, lane: usize, row: i32) {
+        let left = (PADDING + lane * LANE_WIDTH) as i32;
+        let top = row + CAR.len() as i32 - 1;
+
+        for (y, line) in CAR.iter().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                if c == 'O' {
+                    self.light(left + x as i32, top - y as i32);
+                }
+            }
+        }
+    }
+
+    /// Rows from the top of the screen down, as drawn.
+    pub fn rows(&self) -> impl Iterator<Item = &[bool]> {
+        self.cells.chunks(self.width).rev()
     }
 }